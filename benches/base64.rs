@@ -0,0 +1,167 @@
+// Compares the table-driven base64 against the implementation it replaced.
+// Run with `cargo bench --bench base64`.
+use crypto::encoding::base64;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SIZE: usize = 1 << 20;
+const ROUNDS: u32 = 20;
+
+fn main() {
+    let data: Vec<u8> = (0..SIZE).map(|i| (i * 7 + i / 251) as u8).collect();
+    let encoded = base64::encode(&data);
+    assert_eq!(legacy::encode(&data), encoded);
+    assert_eq!(legacy::decode(&encoded).unwrap(), data);
+
+    report(
+        "encode",
+        || legacy::encode(black_box(&data)),
+        || base64::encode(black_box(&data)),
+    );
+    report(
+        "decode",
+        || legacy::decode(black_box(&encoded)),
+        || base64::decode(black_box(&encoded)).ok(),
+    );
+}
+
+fn report<A, B>(name: &str, legacy: impl Fn() -> A, table: impl Fn() -> B) {
+    let legacy = time(legacy);
+    let table = time(table);
+    println!(
+        "{}: legacy {:.1} MiB/s, table {:.1} MiB/s ({:.1}x)",
+        name,
+        throughput(legacy),
+        throughput(table),
+        legacy.as_secs_f64() / table.as_secs_f64()
+    );
+}
+
+fn time<T>(f: impl Fn() -> T) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(f());
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn throughput(per_round: Duration) -> f64 {
+    SIZE as f64 / (1 << 20) as f64 / per_round.as_secs_f64()
+}
+
+/// The base64 implementation before the lookup tables, verbatim.
+#[allow(clippy::all)]
+mod legacy {
+    static TABLE: [char; 65] = [
+        'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R',
+        'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j',
+        'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '0', '1',
+        '2', '3', '4', '5', '6', '7', '8', '9', '+', '/', '=',
+    ];
+
+    pub fn encode(data: &[u8]) -> String {
+        let encoded = raw_encode(data);
+        let mut string = String::with_capacity(data.len() * (4 / 3));
+        for byte in encoded {
+            string.push(TABLE[byte as usize])
+        }
+        string
+    }
+
+    fn raw_encode(data: &[u8]) -> Vec<u8> {
+        let mut result = vec![];
+        for i in (0..data.len()).step_by(3) {
+            let first_byte = data.get(i);
+            let second_byte = data.get(i + 1);
+            let third_byte = data.get(i + 2);
+
+            for byte in &encode_group(&[first_byte, second_byte, third_byte]) {
+                result.push(*byte)
+            }
+        }
+
+        result
+    }
+
+    fn encode_group(bytes: &[Option<&u8>; 3]) -> [u8; 4] {
+        let first = bytes[0].unwrap();
+        let second = bytes[1].unwrap_or(&0);
+        let third = bytes[2].unwrap_or(&0);
+        let second_is_some = bytes[1].is_some();
+        let third_is_some = bytes[2].is_some();
+
+        [
+            (first & 0b_1111_1100) >> 2,
+            (first & 0b_0000_0011) << 4 | (second & 0b_1111_0000) >> 4,
+            match second_is_some {
+                true => (second & 0b_0000_1111) << 2 | (third & 0b_1100_0000) >> 6,
+                _ => 64,
+            },
+            match third_is_some {
+                true => third & 0b_0011_1111,
+                _ => 64,
+            },
+        ]
+    }
+
+    pub fn decode(data: &str) -> Option<Vec<u8>> {
+        if data.len() % 4 != 0 {
+            return None;
+        }
+        decode_raw(data.as_bytes())
+    }
+
+    fn decode_raw(bytes: &[u8]) -> Option<Vec<u8>> {
+        let non_ascii = match ascii_to_base64(bytes) {
+            Some(bytes) => bytes,
+            None => return None,
+        };
+        let mut decoded = Vec::with_capacity(bytes.len() * (3 / 4));
+        for i in (0..non_ascii.len()).step_by(4) {
+            let decoded_group = decode_group(&[
+                non_ascii[i],
+                non_ascii[i + 1],
+                non_ascii[i + 2],
+                non_ascii[i + 3],
+            ]);
+            let decoded_group: Vec<u8> = decoded_group
+                .iter()
+                .filter(|b| b.is_some())
+                .map(|b| b.unwrap())
+                .collect();
+            decoded.extend_from_slice(&decoded_group);
+        }
+        Some(decoded)
+    }
+
+    fn ascii_to_base64(bytes: &[u8]) -> Option<Vec<u8>> {
+        let mut non_ascii = Vec::with_capacity(bytes.len());
+        for b in bytes {
+            non_ascii.push(match *b as char {
+                'A'..='Z' => b - 65,
+                'a'..='z' => b - 71,
+                '0'..='9' => b + 4,
+                '+' => 62,
+                '/' => 63,
+                '=' => 64,
+                _ => return None,
+            });
+        }
+        Some(non_ascii)
+    }
+
+    fn decode_group(bytes: &[u8; 4]) -> [Option<u8>; 3] {
+        let (first, second, third, fourth) = (bytes[0], bytes[1], bytes[2], bytes[3]);
+        [
+            Some((first & 0b_0011_1111) << 2 | (second & 0b_0011_0000) >> 4),
+            match third {
+                64 => None,
+                _ => Some((second & 0b_0000_1111) << 4 | (third & 0b_0011_1100) >> 2),
+            },
+            match fourth {
+                64 => None,
+                _ => Some((third & 0b_0000_0011) << 6 | fourth),
+            },
+        ]
+    }
+}
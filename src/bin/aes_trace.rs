@@ -0,0 +1,95 @@
+//! Prints FIPS-197 style traces of `symm::aes::Cipher`,
+//! and compares traces to find where two AES ports stop agreeing.
+//!
+//! Usage: aes_trace [-d] [--rounds N] KEY BLOCK
+//!        aes_trace --diff EXPECTED ACTUAL
+
+use crypto::encoding::hex;
+use crypto::symm::aes::{diff, Cipher, Trace};
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "usage: aes_trace [-d] [--rounds N] KEY BLOCK
+       aes_trace --diff EXPECTED ACTUAL";
+
+fn main() {
+    let mut decrypt = false;
+    let mut rounds = None;
+    let mut compare = false;
+    let mut operands = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-d" | "--decrypt" => decrypt = true,
+            "--rounds" => rounds = Some(parameter(args.next())),
+            "--diff" => compare = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => usage(),
+            _ => operands.push(arg),
+        }
+    }
+    if operands.len() != 2 {
+        usage();
+    }
+
+    if compare {
+        let expected = read_trace(&operands[0]);
+        let actual = read_trace(&operands[1]);
+        if let Some(divergence) = diff(&expected, &actual) {
+            println!("{}", divergence);
+            process::exit(1);
+        }
+        println!("traces match, {} entries", expected.entries.len());
+        return;
+    }
+
+    let key = bytes(&operands[0]);
+    let block = bytes(&operands[1]);
+    if ![16, 24, 32].contains(&key.len()) || block.len() != 16 {
+        eprintln!("the key must be 16, 24 or 32 bytes and the block 16");
+        process::exit(2);
+    }
+    let mut cipher = match rounds {
+        Some(rounds) => Cipher::new_with_rounds(&key, rounds, false),
+        None => Cipher::new(&key),
+    };
+    cipher.start_tracing();
+    cipher.set_state(&block);
+    if decrypt {
+        cipher.decrypt();
+    } else {
+        cipher.encrypt();
+    }
+    print!("{}", cipher.take_trace().unwrap_or_default());
+}
+
+fn read_trace(path: &str) -> Trace {
+    let text = fs::read_to_string(path).unwrap_or_else(|e| fail(path, e));
+    text.parse().unwrap_or_else(|e| fail(path, e))
+}
+
+fn bytes(arg: &str) -> Vec<u8> {
+    hex::from_string(arg).unwrap_or_else(|e| fail(arg, e))
+}
+
+fn parameter(arg: Option<String>) -> u8 {
+    match arg.map(|a| a.parse::<u8>()) {
+        Some(Ok(p)) if p > 0 => p,
+        _ => usage(),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2)
+}
+
+fn fail(what: &str, e: impl std::fmt::Display) -> ! {
+    eprintln!("{}: {}", what, e);
+    process::exit(1)
+}
@@ -0,0 +1,71 @@
+//! Trains a byte level n-gram model on some corpus files
+//! and writes it where `frequency::Model::load` can read it.
+//!
+//! Usage: train_model [--fold-case] [--floor F | --add-k K] -o MODEL CORPUS...
+
+use crypto::frequency::model::{Model, Smoothing};
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "usage: train_model [--fold-case] [--floor F | --add-k K] -o MODEL CORPUS...";
+
+fn main() {
+    let mut fold_case = false;
+    let mut smoothing = Smoothing::default();
+    let mut output = None;
+    let mut corpora = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fold-case" => fold_case = true,
+            "--floor" => smoothing = Smoothing::Floor(parameter(args.next())),
+            "--add-k" => smoothing = Smoothing::AddK(parameter(args.next())),
+            "-o" | "--output" => output = Some(args.next().unwrap_or_else(|| usage())),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => usage(),
+            _ => corpora.push(arg),
+        }
+    }
+    let output = output.unwrap_or_else(|| usage());
+    if corpora.is_empty() {
+        usage();
+    }
+
+    let mut model = Model::new(fold_case).with_smoothing(smoothing);
+    let mut size = 0;
+    for corpus in &corpora {
+        let text = fs::read(corpus).unwrap_or_else(|e| fail(corpus, e));
+        size += text.len();
+        model.update(&text);
+    }
+    model.save(&output).unwrap_or_else(|e| fail(&output, e));
+    eprintln!(
+        "trained on {} bytes from {} files, wrote {} bytes to {}",
+        size,
+        corpora.len(),
+        model.to_bytes().len(),
+        output
+    );
+}
+
+fn parameter(arg: Option<String>) -> f64 {
+    match arg.map(|a| a.parse::<f64>()) {
+        Some(Ok(p)) if p >= 0.0 => p,
+        _ => usage(),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2)
+}
+
+fn fail(path: &str, e: std::io::Error) -> ! {
+    eprintln!("{}: {}", path, e);
+    process::exit(1)
+}
@@ -1,3 +1,30 @@
+use std::{error, fmt, slice};
+
+/// Two buffers which should have been the same length, but were not.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LengthMismatch {
+    pub left: usize,
+    pub right: usize,
+}
+
+impl fmt::Display for LengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "length mismatch: {} != {}", self.left, self.right)
+    }
+}
+
+impl error::Error for LengthMismatch {}
+
+fn same_length(left: &[u8], right: &[u8]) -> Result<(), LengthMismatch> {
+    if left.len() != right.len() {
+        return Err(LengthMismatch {
+            left: left.len(),
+            right: right.len(),
+        });
+    }
+    Ok(())
+}
+
 /// XORs all bytes in bigger with smaller, cycling through.
 /// If `smaller > bigger`, as most of smaller as possible will be xored
 /// agains bigger, effectively switching places.
@@ -13,6 +40,99 @@ pub fn xor(v: &[u8], vv: &[u8]) -> Vec<u8> {
     repeating_xor(v, vv)
 }
 
+/// XORs two buffers of the same length.
+pub fn fixed_xor(left: &[u8], right: &[u8]) -> Result<Vec<u8>, LengthMismatch> {
+    same_length(left, right)?;
+    Ok(left.iter().zip(right).map(|(l, r)| l ^ r).collect())
+}
+
+/// Like `repeating_xor`, but without allocating:
+/// XORs `key` into `buffer`, cycling through it.
+pub fn xor_in_place(buffer: &mut [u8], key: &[u8]) {
+    for (byte, key_byte) in buffer.iter_mut().zip(key.iter().cycle()) {
+        *byte ^= key_byte;
+    }
+}
+
+/// The number of differing bits between two buffers of the same length.
+pub fn hamming_distance(left: &[u8], right: &[u8]) -> Result<usize, LengthMismatch> {
+    same_length(left, right)?;
+    Ok(left
+        .iter()
+        .zip(right)
+        .map(|(l, r)| (l ^ r).count_ones() as usize)
+        .sum())
+}
+
+/// Compares two buffers in time which only depends on their length,
+/// so comparing MACs doesn't leak how much of them is right.
+pub fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+    let difference = left.iter().zip(right).fold(0, |acc, (l, r)| acc | (l ^ r));
+    std::hint::black_box(difference) == 0
+}
+
+/// Blocks of `block_size` bytes, the last one possibly shorter.
+pub struct Blocks<'a> {
+    chunks: slice::Chunks<'a, u8>,
+}
+
+impl<'a> Iterator for Blocks<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.chunks.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for Blocks<'a> {}
+
+/// Iterates over the blocks of `data`.
+/// Panics if `block_size` is 0.
+pub fn blocks(data: &[u8], block_size: usize) -> Blocks<'_> {
+    Blocks {
+        chunks: data.chunks(block_size),
+    }
+}
+
+/// Transposes the blocks of `data`: the i-th result has the i-th byte
+/// of every block. With a repeating key XOR of `block_size` bytes,
+/// each of them is single byte XORed.
+pub fn transpose(data: &[u8], block_size: usize) -> Vec<Vec<u8>> {
+    let mut transposed = vec![Vec::with_capacity(data.len() / block_size + 1); block_size];
+    for block in blocks(data, block_size) {
+        for (column, &b) in transposed.iter_mut().zip(block) {
+            column.push(b);
+        }
+    }
+    transposed.retain(|column| !column.is_empty());
+    transposed
+}
+
+/// Whether all bytes are printable ASCII, counting
+/// tabs and line breaks as printable.
+pub fn is_printable_ascii(bytes: &[u8]) -> bool {
+    bytes.iter().all(|&b| is_printable(b))
+}
+
+/// The fraction of printable ASCII bytes, 1 for no bytes.
+pub fn printable_ratio(bytes: &[u8]) -> f64 {
+    if bytes.is_empty() {
+        return 1.0;
+    }
+    bytes.iter().filter(|&&b| is_printable(b)).count() as f64 / bytes.len() as f64
+}
+
+fn is_printable(b: u8) -> bool {
+    matches!(b, b' '..=b'~' | b'\t' | b'\n' | b'\r')
+}
+
 // Return an UTF8 encoded string from the bytes,
 // if it can.
 pub fn to_string(bytes: &[u8]) -> Option<String> {
@@ -34,4 +154,61 @@ mod tests {
             vec![0b10100100, 0b10011010]
         )
     }
+
+    #[test]
+    fn test_fixed_xor() {
+        let left = hex::from_string("1c0111001f010100061a024b53535009181c").unwrap();
+        let right = hex::from_string("686974207468652062756c6c277320657965").unwrap();
+        let xored = fixed_xor(&left, &right).unwrap();
+        assert_eq!(
+            hex::to_string(&xored),
+            "746865206B696420646F6E277420706C6179"
+        );
+        assert_eq!(
+            fixed_xor(&left, &right[1..]),
+            Err(LengthMismatch {
+                left: 18,
+                right: 17
+            })
+        );
+
+        let mut buffer = left.clone();
+        xor_in_place(&mut buffer, &right);
+        assert_eq!(buffer, xored);
+    }
+
+    #[test]
+    fn test_hamming_distance() {
+        assert_eq!(
+            hamming_distance(b"this is a test", b"wokka wokka!!!"),
+            Ok(37)
+        );
+        assert!(hamming_distance(b"a", b"").is_err());
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"mac", b"mac"));
+        assert!(!constant_time_eq(b"mac", b"maC"));
+        assert!(!constant_time_eq(b"mac", b"ma"));
+    }
+
+    #[test]
+    fn test_blocks_and_transpose() {
+        let data = b"abcdefgh";
+        let blocks: Vec<&[u8]> = blocks(data, 3).collect();
+        assert_eq!(blocks, vec![&b"abc"[..], b"def", b"gh"]);
+        assert_eq!(
+            transpose(data, 3),
+            vec![b"adg".to_vec(), b"beh".to_vec(), b"cf".to_vec()]
+        );
+        assert_eq!(transpose(b"ab", 4), vec![b"a".to_vec(), b"b".to_vec()]);
+    }
+
+    #[test]
+    fn test_printable() {
+        assert!(is_printable_ascii(b"Hello,\tWorld!\r\n"));
+        assert!(!is_printable_ascii(b"Hello\x00"));
+        assert_eq!(printable_ratio(b"ab\x01\xff"), 0.5);
+    }
 }
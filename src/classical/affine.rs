@@ -0,0 +1,59 @@
+use super::{map_letters, score};
+use crate::frequency::Model;
+
+/// The multiplicative inverse of `a` modulo 26, if there is one.
+fn inverse(a: u8) -> Option<u8> {
+    (1..26).find(|&x| (a as u16 * x as u16) % 26 == 1)
+}
+
+/// Maps every letter `x` to `a * x + b`.
+/// None if `a` has no inverse modulo 26, as then
+/// there is no way to decrypt.
+pub fn encrypt(plain_text: &str, a: u8, b: u8) -> Option<String> {
+    inverse(a % 26)?;
+    let (a, b) = (a as u16 % 26, b as u16 % 26);
+    Some(map_letters(plain_text, |l| ((a * l as u16 + b) % 26) as u8))
+}
+
+pub fn decrypt(cipher_text: &str, a: u8, b: u8) -> Option<String> {
+    let a_inverse = inverse(a % 26)? as u16;
+    let b = b as u16 % 26;
+    Some(map_letters(cipher_text, |l| {
+        (a_inverse * (l as u16 + 26 - b) % 26) as u8
+    }))
+}
+
+/// Tries the 312 keys and returns the best one for the model,
+/// and its plaintext.
+pub fn solve(cipher_text: &str, model: &Model) -> (u8, u8, String) {
+    (1..26)
+        .filter(|&a| inverse(a).is_some())
+        .flat_map(|a| (0..26).map(move |b| (a, b)))
+        .map(|(a, b)| (a, b, decrypt(cipher_text, a, b).unwrap()))
+        .max_by(|(_, _, x), (_, _, y)| score(model, x).total_cmp(&score(model, y)))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frequency;
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let cipher_text = encrypt("Affine cipher", 5, 8).unwrap();
+        assert_eq!(cipher_text, "Ihhwvc swfrcp");
+        assert_eq!(decrypt(&cipher_text, 5, 8).unwrap(), "Affine cipher");
+        assert_eq!(encrypt("nope", 13, 1), None);
+    }
+
+    #[test]
+    fn test_solve() {
+        let plain_text = "whether that nation might live and endure";
+        let cipher_text = encrypt(plain_text, 7, 22).unwrap();
+        assert_eq!(
+            solve(&cipher_text, frequency::english()),
+            (7, 22, plain_text.into())
+        );
+    }
+}
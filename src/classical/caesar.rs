@@ -0,0 +1,42 @@
+use super::{map_letters, score};
+use crate::frequency::Model;
+
+/// Shifts every letter `shift` places along the alphabet.
+pub fn encrypt(plain_text: &str, shift: u8) -> String {
+    map_letters(plain_text, |l| l + shift % 26)
+}
+
+pub fn decrypt(cipher_text: &str, shift: u8) -> String {
+    encrypt(cipher_text, 26 - shift % 26)
+}
+
+/// Tries the 26 shifts and returns the one whose plaintext
+/// the model likes best, and the plaintext.
+pub fn solve(cipher_text: &str, model: &Model) -> (u8, String) {
+    (0..26)
+        .map(|shift| (shift, decrypt(cipher_text, shift)))
+        .max_by(|(_, a), (_, b)| score(model, a).total_cmp(&score(model, b)))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frequency;
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let cipher_text = encrypt("Veni, vidi, vici. Zzz", 3);
+        assert_eq!(cipher_text, "Yhql, ylgl, ylfl. Ccc");
+        assert_eq!(decrypt(&cipher_text, 3), "Veni, vidi, vici. Zzz");
+        assert_eq!(encrypt("abc", 29), "def");
+    }
+
+    #[test]
+    fn test_solve() {
+        let plain_text = "THE QUICK BROWN FOX JUMPS OVER THE LAZY DOG";
+        let (shift, solved) = solve(&encrypt(plain_text, 13), frequency::english());
+        assert_eq!(shift, 13);
+        assert_eq!(solved, plain_text);
+    }
+}
@@ -0,0 +1,38 @@
+//! Pen and paper ciphers over the 26 letters of the latin alphabet,
+//! and solvers for them which score candidates with `frequency` models.
+//! Letters keep their case; everything else goes through untouched.
+
+use crate::frequency::Model;
+
+pub mod affine;
+pub mod caesar;
+pub mod substitution;
+pub mod vigenere;
+
+/// Maps every ASCII letter of the text through `f`, which takes and
+/// returns its index in the alphabet (0 for a, 25 for z).
+/// The letters keep their case and nothing else changes.
+fn map_letters(text: &str, mut f: impl FnMut(u8) -> u8) -> String {
+    text.chars()
+        .map(|c| match c {
+            'a'..='z' => (b'a' + f(c as u8 - b'a') % 26) as char,
+            'A'..='Z' => (b'A' + f(c as u8 - b'A') % 26) as char,
+            _ => c,
+        })
+        .collect()
+}
+
+/// The indices in the alphabet of the letters in the text,
+/// ignoring case and skipping everything else.
+fn letters(text: &str) -> Vec<u8> {
+    text.bytes()
+        .filter(u8::is_ascii_alphabetic)
+        .map(|b| b.to_ascii_lowercase() - b'a')
+        .collect()
+}
+
+/// Scores a candidate plaintext. Ciphertexts are often all uppercase,
+/// so the case of the candidate is not to be trusted.
+fn score(model: &Model, text: &str) -> f64 {
+    model.score(text.to_ascii_lowercase().as_bytes())
+}
@@ -0,0 +1,189 @@
+use super::{letters, map_letters, score};
+use crate::frequency::Model;
+use crate::random;
+
+/// How many swaps in a row can fail to improve a key
+/// before the hill climbing gives up.
+const PATIENCE: usize = 1000;
+
+/// A monoalphabetic substitution key: the letter every
+/// letter of the alphabet is replaced with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    substitutes: [u8; 26],
+}
+
+impl Key {
+    /// A key from the letters that replace `abc...z`, in order.
+    /// None unless it's a permutation of the alphabet.
+    pub fn new(alphabet: &str) -> Option<Self> {
+        let letters = letters(alphabet);
+        if alphabet.len() != 26 || letters.len() != 26 {
+            return None;
+        }
+        let mut substitutes = [0; 26];
+        let mut seen = [false; 26];
+        for (substitute, &l) in substitutes.iter_mut().zip(&letters) {
+            if seen[l as usize] {
+                return None;
+            }
+            seen[l as usize] = true;
+            *substitute = l;
+        }
+        Some(Key { substitutes })
+    }
+
+    /// A random key, from `random`.
+    pub fn random() -> Self {
+        let mut substitutes = [0; 26];
+        for (i, s) in substitutes.iter_mut().enumerate() {
+            *s = i as u8;
+        }
+        // Fisher-Yates
+        for i in (1..26).rev() {
+            substitutes.swap(i, random::in_range(0, i + 1));
+        }
+        Key { substitutes }
+    }
+
+    /// The key that undoes this one.
+    pub fn inverse(&self) -> Self {
+        let mut substitutes = [0; 26];
+        for (i, &s) in self.substitutes.iter().enumerate() {
+            substitutes[s as usize] = i as u8;
+        }
+        Key { substitutes }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.substitutes.swap(a, b);
+    }
+}
+
+impl std::fmt::Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for &s in self.substitutes.iter() {
+            write!(f, "{}", (b'a' + s) as char)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn encrypt(plain_text: &str, key: &Key) -> String {
+    map_letters(plain_text, |l| key.substitutes[l as usize])
+}
+
+pub fn decrypt(cipher_text: &str, key: &Key) -> String {
+    encrypt(cipher_text, &key.inverse())
+}
+
+/// Hill climbing: start from the key that maps the letters of the
+/// ciphertext to the model's by frequency and keep swapping pairs of
+/// letters while the model likes the plaintext better. After the first
+/// climb, `restarts` more start from random keys, drawn from `random`,
+/// in case the first got stuck on a local maximum.
+/// Returns the key and the plaintext.
+pub fn solve(cipher_text: &str, model: &Model, restarts: usize) -> (Key, String) {
+    let mut best = climb(cipher_text, model, frequency_key(cipher_text, model));
+    for _ in 0..restarts {
+        let attempt = climb(cipher_text, model, Key::random());
+        if attempt.0 > best.0 {
+            best = attempt;
+        }
+    }
+    let (_, key) = best;
+    (key, decrypt(cipher_text, &key))
+}
+
+fn climb(cipher_text: &str, model: &Model, mut key: Key) -> (f64, Key) {
+    // climb on the decryption key, it's what we score with
+    let mut best = score(model, &encrypt(cipher_text, &key.inverse()));
+    let mut failures = 0;
+    while failures < PATIENCE {
+        let a = random::in_range(0, 26);
+        let b = random::in_range(0, 26);
+        if a == b {
+            continue;
+        }
+        let mut attempt = key;
+        attempt.swap(a, b);
+        let attempt_score = score(model, &encrypt(cipher_text, &attempt.inverse()));
+        if attempt_score > best {
+            best = attempt_score;
+            key = attempt;
+            failures = 0;
+        } else {
+            failures += 1;
+        }
+    }
+    (best, key)
+}
+
+/// The key which replaces the most common letter of the model with
+/// the most common of the ciphertext, and so on.
+fn frequency_key(cipher_text: &str, model: &Model) -> Key {
+    let by_frequency = |count: &dyn Fn(u8) -> u64| -> Vec<u8> {
+        let mut alphabet: Vec<u8> = (0..26).collect();
+        alphabet.sort_by_key(|&l| std::cmp::Reverse(count(l)));
+        alphabet
+    };
+    let mut counts = [0; 26];
+    for l in letters(cipher_text) {
+        counts[l as usize] += 1;
+    }
+    let plain = by_frequency(&|l| model.count(&[b'a' + l]) + model.count(&[b'A' + l]));
+    let cipher = by_frequency(&|l| counts[l as usize]);
+
+    let mut substitutes = [0; 26];
+    for (&p, &c) in plain.iter().zip(&cipher) {
+        substitutes[p as usize] = c;
+    }
+    Key { substitutes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frequency;
+
+    #[test]
+    fn test_key() {
+        let key = Key::new("QWERTYUIOPASDFGHJKLZXCVBNM").unwrap();
+        assert_eq!(key.to_string(), "qwertyuiopasdfghjklzxcvbnm");
+        assert_eq!(key.inverse().inverse(), key);
+        assert_eq!(Key::new("QWERTYUIOPASDFGHJKLZXCVBNN"), None);
+        assert_eq!(Key::new("QWERTY"), None);
+
+        let cipher_text = encrypt("Hello, World!", &key);
+        assert_eq!(cipher_text, "Itssg, Vgksr!");
+        assert_eq!(decrypt(&cipher_text, &key), "Hello, World!");
+    }
+
+    #[test]
+    fn test_random_key() {
+        let key = random::with_rng(42, Key::random);
+        assert_eq!(key.inverse().inverse(), key);
+        assert_eq!(key, random::with_rng(42, Key::random));
+    }
+
+    #[test]
+    fn test_solve() {
+        let plain_text = "to sherlock holmes she is always the woman. i have seldom heard him \
+            mention her under any other name. in his eyes she eclipses and predominates the \
+            whole of her sex. it was not that he felt any emotion akin to love for irene adler. \
+            all emotions, and that one particularly, were abhorrent to his cold, precise but \
+            admirably balanced mind. he was, i take it, the most perfect reasoning and \
+            observing machine that the world has seen, but as a lover he would have placed \
+            himself in a false position.";
+        let key = Key::new("QWERTYUIOPASDFGHJKLZXCVBNM").unwrap();
+        let cipher_text = encrypt(plain_text, &key);
+        let (_, solved) = random::with_rng(1, || solve(&cipher_text, frequency::english(), 2));
+        // rare letters like x and q can't be told apart, but the rest is solved
+        let wrong = solved
+            .chars()
+            .zip(plain_text.chars())
+            .filter(|(a, b)| a != b)
+            .count();
+        assert!(wrong <= 2, "{}", solved);
+    }
+}
@@ -0,0 +1,183 @@
+use super::{letters, map_letters, score};
+use crate::frequency::{index_of_coincidence, Model};
+use std::collections::HashMap;
+
+/// Lengths of key tried by `solve` when not told otherwise.
+pub const MAX_KEY_LENGTH: usize = 20;
+
+/// The shifts of a key of letters, None if it's empty
+/// or has anything else.
+fn shifts(key: &str) -> Option<Vec<u8>> {
+    if key.is_empty() || !key.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some(letters(key))
+}
+
+/// Shifts every letter by the next letter of the key, cycling through it.
+/// Only letters of the text use up the key.
+pub fn encrypt(plain_text: &str, key: &str) -> Option<String> {
+    let shifts = shifts(key)?;
+    let mut key = shifts.iter().cycle();
+    Some(map_letters(plain_text, |l| l + key.next().unwrap()))
+}
+
+pub fn decrypt(cipher_text: &str, key: &str) -> Option<String> {
+    let shifts = shifts(key)?;
+    let mut key = shifts.iter().cycle();
+    Some(map_letters(cipher_text, |l| l + 26 - key.next().unwrap()))
+}
+
+/// The letters that each letter of a key of this length encrypts.
+fn columns(letters: &[u8], key_length: usize) -> Vec<Vec<u8>> {
+    let mut columns = vec![Vec::new(); key_length];
+    for (i, &l) in letters.iter().enumerate() {
+        columns[i % key_length].push(l);
+    }
+    columns
+}
+
+/// Key lengths up to `max` ranked by the mean index of coincidence of
+/// their columns: with the right length, each column is a Caesar cipher
+/// and looks like the language, while the rest look random.
+pub fn key_lengths_by_ioc(cipher_text: &str, max: usize) -> Vec<(usize, f64)> {
+    let letters = letters(cipher_text);
+    let mut lengths: Vec<(usize, f64)> = (1..=max.min(letters.len() / 2).max(1))
+        .map(|length| {
+            let columns = columns(&letters, length);
+            let ioc = columns.iter().map(|c| index_of_coincidence(c)).sum::<f64>() / length as f64;
+            (length, ioc)
+        })
+        .collect();
+    lengths.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    lengths
+}
+
+/// Kasiski examination: repeated trigrams are often the same plaintext
+/// encrypted with the same part of the key, so the distances between them
+/// are multiples of the key length. Returns lengths from 2 to `max`
+/// ranked by how many distances they divide.
+pub fn kasiski(cipher_text: &str, max: usize) -> Vec<(usize, usize)> {
+    let letters = letters(cipher_text);
+    let mut seen: HashMap<&[u8], usize> = HashMap::new();
+    let mut distances = Vec::new();
+    for (i, trigram) in letters.windows(3).enumerate() {
+        if let Some(previous) = seen.insert(trigram, i) {
+            distances.push(i - previous);
+        }
+    }
+    let mut lengths: Vec<(usize, usize)> = (2..=max)
+        .map(|length| {
+            let divides = distances
+                .iter()
+                .filter(|&&d| d.is_multiple_of(length))
+                .count();
+            (length, divides)
+        })
+        .filter(|&(_, divides)| divides > 0)
+        .collect();
+    lengths.sort_by(|(_, a), (_, b)| b.cmp(a));
+    lengths
+}
+
+/// Finds the key of a Vigenère cipher of at most `max_key_length` letters.
+/// Each column of the best key lengths is solved as a Caesar cipher
+/// by chi-squared against the model, and the key whose plaintext the
+/// model likes best wins. Returns the key and the plaintext.
+pub fn solve(cipher_text: &str, model: &Model, max_key_length: usize) -> Option<(String, String)> {
+    let letters = letters(cipher_text);
+    if letters.is_empty() {
+        return None;
+    }
+
+    let mut candidates: Vec<usize> = key_lengths_by_ioc(cipher_text, max_key_length)
+        .into_iter()
+        .take(3)
+        .map(|(length, _)| length)
+        .collect();
+    candidates.extend(
+        kasiski(cipher_text, max_key_length)
+            .iter()
+            .take(3)
+            .map(|&(l, _)| l),
+    );
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    candidates
+        .into_iter()
+        .map(|length| {
+            let key = shortest_period(&solve_key(&letters, length, model));
+            let plain_text = decrypt(cipher_text, &key).unwrap();
+            (key, plain_text)
+        })
+        .max_by(|(_, a), (_, b)| score(model, a).total_cmp(&score(model, b)))
+}
+
+fn solve_key(letters: &[u8], key_length: usize, model: &Model) -> String {
+    columns(letters, key_length)
+        .iter()
+        .map(|column| {
+            let chi_squared = |shift: u8| -> f64 {
+                let column: Vec<u8> = column
+                    .iter()
+                    .map(|l| b'a' + (l + 26 - shift) % 26)
+                    .collect();
+                model.chi_squared(&column)
+            };
+            let shift = (0..26)
+                .min_by(|&a, &b| chi_squared(a).total_cmp(&chi_squared(b)))
+                .unwrap();
+            (b'A' + shift) as char
+        })
+        .collect()
+}
+
+/// A key repeating itself, like ABCABC, is just as good as ABC.
+fn shortest_period(key: &str) -> String {
+    let bytes = key.as_bytes();
+    let period = (1..bytes.len())
+        .filter(|&p| bytes.len().is_multiple_of(p))
+        .find(|&p| bytes.chunks(p).all(|chunk| chunk == &bytes[..p]))
+        .unwrap_or(bytes.len());
+    key[..period].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frequency;
+
+    const PLAIN_TEXT: &str = "To Sherlock Holmes she is always the woman. I have seldom heard \
+        him mention her under any other name. In his eyes she eclipses and predominates the \
+        whole of her sex. It was not that he felt any emotion akin to love for Irene Adler. \
+        All emotions, and that one particularly, were abhorrent to his cold, precise but \
+        admirably balanced mind.";
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let cipher_text = encrypt("Attack at dawn!", "LEMON").unwrap();
+        assert_eq!(cipher_text, "Lxfopv ef rnhr!");
+        assert_eq!(decrypt(&cipher_text, "lemon").unwrap(), "Attack at dawn!");
+        assert_eq!(encrypt("text", "no spaces"), None);
+        assert_eq!(encrypt("text", ""), None);
+    }
+
+    #[test]
+    fn test_key_length() {
+        let cipher_text = encrypt(PLAIN_TEXT, "CRYPTO").unwrap();
+        let by_ioc = key_lengths_by_ioc(&cipher_text, MAX_KEY_LENGTH);
+        assert_eq!(by_ioc[0].0 % 6, 0);
+        assert!(kasiski(&cipher_text, MAX_KEY_LENGTH)[..3]
+            .iter()
+            .any(|&(length, _)| length == 6));
+    }
+
+    #[test]
+    fn test_solve() {
+        let cipher_text = encrypt(PLAIN_TEXT, "CRYPTO").unwrap();
+        let (key, plain_text) = solve(&cipher_text, frequency::english(), MAX_KEY_LENGTH).unwrap();
+        assert_eq!(key, "CRYPTO");
+        assert_eq!(plain_text, PLAIN_TEXT);
+    }
+}
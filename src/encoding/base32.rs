@@ -0,0 +1,188 @@
+use std::{error, fmt};
+
+type Result<T> = std::result::Result<T, DecodeError>;
+
+/// The padding character, `=`.
+const PAD: u8 = b'=';
+
+/// A flavour of base32: an alphabet and whether the output is padded.
+pub struct Engine {
+    alphabet: &'static [u8; 32],
+    pad: bool,
+}
+
+/// Base32, RFC 4648 section 6.
+pub static STANDARD: Engine = Engine {
+    alphabet: b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567",
+    pad: true,
+};
+
+/// Base32 with the extended hex alphabet, RFC 4648 section 7.
+pub static HEX: Engine = Engine {
+    alphabet: b"0123456789ABCDEFGHIJKLMNOPQRSTUV",
+    pad: true,
+};
+
+/// Encodes arbitrary bytes to a standard base32 string.
+pub fn encode(data: &[u8]) -> String {
+    STANDARD.encode(data)
+}
+
+/// Decode a standard base32 string to bytes.
+pub fn decode(data: &str) -> Result<Vec<u8>> {
+    STANDARD.decode(data)
+}
+
+/// Why a base32 string could not be decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeErrorKind {
+    /// Not in the alphabet of the engine.
+    InvalidByte(u8),
+    /// Base32 can't be this long, or short.
+    InvalidLength,
+    /// A `=` where it should not be, or not one where it should.
+    InvalidPadding,
+    /// The last character has bits set which do not encode anything.
+    TrailingBits,
+}
+
+/// An error decoding base32, with the position of the
+/// offending byte in the input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    pub position: usize,
+    pub kind: DecodeErrorKind,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            DecodeErrorKind::InvalidByte(b) => {
+                write!(f, "invalid byte 0x{:02x} at position {}", b, self.position)
+            }
+            DecodeErrorKind::InvalidLength => {
+                write!(f, "invalid length, ended at position {}", self.position)
+            }
+            DecodeErrorKind::InvalidPadding => {
+                write!(f, "invalid padding at position {}", self.position)
+            }
+            DecodeErrorKind::TrailingBits => {
+                write!(f, "trailing bits set at position {}", self.position)
+            }
+        }
+    }
+}
+
+impl error::Error for DecodeError {}
+
+impl Engine {
+    /// Encodes arbitrary bytes to a base32 string.
+    /// Every five bits are a character, every eight characters a group.
+    pub fn encode(&self, data: &[u8]) -> String {
+        let mut encoded = String::with_capacity(data.len().div_ceil(5) * 8);
+        let mut buffer: u16 = 0;
+        let mut bits = 0;
+        for &b in data {
+            buffer = buffer << 8 | b as u16;
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                encoded.push(self.alphabet[(buffer >> bits & 0x1F) as usize] as char);
+            }
+        }
+        if bits > 0 {
+            encoded.push(self.alphabet[(buffer << (5 - bits) & 0x1F) as usize] as char);
+        }
+        while self.pad && !encoded.len().is_multiple_of(8) {
+            encoded.push(PAD as char);
+        }
+        encoded
+    }
+
+    /// Decode a base32 string to bytes.
+    /// As with base64, decoding is strict.
+    pub fn decode(&self, data: &str) -> Result<Vec<u8>> {
+        let bytes = data.as_bytes();
+        let error = |position, kind| Err(DecodeError { position, kind });
+
+        let data_len = bytes.iter().position(|&b| b == PAD).unwrap_or(bytes.len());
+        if data_len < bytes.len() {
+            if !self.pad {
+                return error(data_len, DecodeErrorKind::InvalidByte(PAD));
+            }
+            let pads = bytes.len() - data_len;
+            if bytes[data_len..].iter().any(|&b| b != PAD)
+                || ![1, 3, 4, 6].contains(&pads)
+                || !bytes.len().is_multiple_of(8)
+            {
+                return error(data_len, DecodeErrorKind::InvalidPadding);
+            }
+        } else if self.pad && !bytes.len().is_multiple_of(8) {
+            return error(bytes.len(), DecodeErrorKind::InvalidLength);
+        }
+        // only these many characters can be left in the last group
+        if [1, 3, 6].contains(&(data_len % 8)) {
+            return error(bytes.len(), DecodeErrorKind::InvalidLength);
+        }
+
+        let mut decoded = Vec::with_capacity(data_len * 5 / 8);
+        let mut buffer: u16 = 0;
+        let mut bits = 0;
+        for (position, &b) in bytes[..data_len].iter().enumerate() {
+            let value = match self.alphabet.iter().position(|&a| a == b) {
+                Some(value) => value as u16,
+                None => return error(position, DecodeErrorKind::InvalidByte(b)),
+            };
+            buffer = (buffer << 5 | value) & 0x0FFF;
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                decoded.push((buffer >> bits) as u8);
+            }
+        }
+        if buffer & ((1 << bits) - 1) != 0 {
+            return error(data_len - 1, DecodeErrorKind::TrailingBits);
+        }
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc4648_vectors() {
+        let vectors = [
+            ("", "", ""),
+            ("f", "MY======", "CO======"),
+            ("fo", "MZXQ====", "CPNG===="),
+            ("foo", "MZXW6===", "CPNMU==="),
+            ("foob", "MZXW6YQ=", "CPNMUOG="),
+            ("fooba", "MZXW6YTB", "CPNMUOJ1"),
+            ("foobar", "MZXW6YTBOI======", "CPNMUOJ1E8======"),
+        ];
+        for (plain, standard, hex) in vectors.iter() {
+            assert_eq!(&encode(plain.as_bytes()), standard);
+            assert_eq!(decode(standard).unwrap(), plain.as_bytes());
+            assert_eq!(&HEX.encode(plain.as_bytes()), hex);
+            assert_eq!(HEX.decode(hex).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let error = |position, kind| Err(DecodeError { position, kind });
+        assert_eq!(decode("MY"), error(2, DecodeErrorKind::InvalidLength));
+        assert_eq!(decode("MY====="), error(2, DecodeErrorKind::InvalidPadding));
+        assert_eq!(
+            decode("M======="),
+            error(1, DecodeErrorKind::InvalidPadding)
+        );
+        assert_eq!(decode("MZ======"), error(1, DecodeErrorKind::TrailingBits));
+        assert_eq!(
+            decode("my======"),
+            error(0, DecodeErrorKind::InvalidByte(b'm'))
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use crate::hash::sha256;
+use std::{error, fmt};
+
+type Result<T> = std::result::Result<T, DecodeError>;
+
+/// The Bitcoin base58 alphabet: no 0, O, I nor l,
+/// which are easy to mix up.
+static ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Why a base58 string could not be decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeErrorKind {
+    /// Not in the alphabet.
+    InvalidByte(u8),
+    /// Too short to have a checksum.
+    InvalidLength,
+    /// The checksum does not match the payload.
+    InvalidChecksum,
+}
+
+/// An error decoding base58, with the position of the
+/// offending byte in the input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    pub position: usize,
+    pub kind: DecodeErrorKind,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            DecodeErrorKind::InvalidByte(b) => {
+                write!(f, "invalid byte 0x{:02x} at position {}", b, self.position)
+            }
+            DecodeErrorKind::InvalidLength => write!(f, "too short for a checksum"),
+            DecodeErrorKind::InvalidChecksum => write!(f, "checksum does not match"),
+        }
+    }
+}
+
+impl error::Error for DecodeError {}
+
+/// Encodes bytes to base58.
+/// The bytes are a big endian number written in base 58,
+/// except for leading zeroes which are each a '1'.
+pub fn encode(data: &[u8]) -> String {
+    let zeroes = data.iter().take_while(|&&b| b == 0).count();
+
+    // little endian digits in base 58
+    let mut digits: Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+    for &b in &data[zeroes..] {
+        let mut carry = b as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let mut encoded = "1".repeat(zeroes);
+    encoded.extend(digits.iter().rev().map(|&d| ALPHABET[d as usize] as char));
+    encoded
+}
+
+/// Decode a base58 string to bytes.
+pub fn decode(data: &str) -> Result<Vec<u8>> {
+    let bytes = data.as_bytes();
+    let ones = bytes.iter().take_while(|&&b| b == b'1').count();
+
+    // little endian bytes
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len() * 733 / 1000 + 1);
+    for (position, &b) in bytes.iter().enumerate().skip(ones) {
+        let mut carry = match ALPHABET.iter().position(|&a| a == b) {
+            Some(value) => value as u32,
+            None => {
+                return Err(DecodeError {
+                    position,
+                    kind: DecodeErrorKind::InvalidByte(b),
+                })
+            }
+        };
+        for byte in decoded.iter_mut() {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            decoded.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    decoded.extend(vec![0; ones]);
+    decoded.reverse();
+    Ok(decoded)
+}
+
+/// Encodes bytes to base58 with a four bytes checksum appended:
+/// the beginning of the double SHA-256 of the data.
+pub fn encode_check(data: &[u8]) -> String {
+    let mut checked = data.to_vec();
+    checked.extend_from_slice(&checksum(data));
+    encode(&checked)
+}
+
+/// Decode a base58 string with a checksum, verifying it.
+pub fn decode_check(data: &str) -> Result<Vec<u8>> {
+    let mut decoded = decode(data)?;
+    if decoded.len() < 4 {
+        return Err(DecodeError {
+            position: data.len(),
+            kind: DecodeErrorKind::InvalidLength,
+        });
+    }
+    let expected = decoded.split_off(decoded.len() - 4);
+    if checksum(&decoded) != expected[..] {
+        return Err(DecodeError {
+            position: data.len(),
+            kind: DecodeErrorKind::InvalidChecksum,
+        });
+    }
+    Ok(decoded)
+}
+
+fn checksum(data: &[u8]) -> [u8; 4] {
+    let hash = sha256::digest(&sha256::digest(data));
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::hex;
+
+    #[test]
+    fn test_encode_decode() {
+        let vectors: [(&[u8], &str); 4] = [
+            (b"", ""),
+            (b"Hello World!", "2NEpo7TZRRrLZSi2U"),
+            (&[0, 0, 1], "112"),
+            (&[0xff; 4], "7YXq9G"),
+        ];
+        for (plain, encoded) in vectors.iter() {
+            assert_eq!(&encode(plain), encoded);
+            assert_eq!(&decode(encoded).unwrap()[..], *plain);
+        }
+    }
+
+    #[test]
+    fn test_bitcoin_address() {
+        // https://en.bitcoin.it/wiki/Technical_background_of_version_1_Bitcoin_addresses
+        let payload = hex::from_string("00010966776006953D5567439E5E39F86A0D273BEE").unwrap();
+        let address = "16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvM";
+        assert_eq!(encode_check(&payload), address);
+        assert_eq!(decode_check(address).unwrap(), payload);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(
+            decode("2NEpo0TZ"),
+            Err(DecodeError {
+                position: 5,
+                kind: DecodeErrorKind::InvalidByte(b'0')
+            })
+        );
+        assert_eq!(
+            decode_check("16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvN")
+                .unwrap_err()
+                .kind,
+            DecodeErrorKind::InvalidChecksum
+        );
+    }
+}
@@ -1,142 +1,512 @@
-/// The base64 alphabet table.
-static TABLE: [char; 65] = [
-    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
-    'T', 'U', 'V', 'W', 'X', 'Y', 'Z', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l',
-    'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '0', '1', '2', '3', '4',
-    '5', '6', '7', '8', '9', '+', '/', '=',
-];
-
-/// Encodes arbitrary bytes to a base64 string.
+use std::io::{self, Read, Write};
+use std::{error, fmt};
+
+type Result<T> = std::result::Result<T, DecodeError>;
+
+/// The standard base64 alphabet, RFC 4648 section 4.
+static STANDARD_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The URL and filename safe alphabet, RFC 4648 section 5.
+static URL_SAFE_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// The padding character, `=`.
+const PAD: u8 = b'=';
+
+/// Marks bytes outside of the alphabet in the decoding tables.
+/// Base64 values only use six bits, so anything with
+/// the highest bit set can't be a value.
+const INVALID: u8 = 0xFF;
+
+/// A flavour of base64: an alphabet, whether the output is padded
+/// and, if it should be wrapped in lines, how long these are.
+pub struct Engine {
+    alphabet: &'static [u8; 64],
+    // the value of every ASCII byte in the alphabet, or INVALID
+    decode_table: [u8; 256],
+    pad: bool,
+    line_length: Option<usize>,
+}
+
+/// Standard base64, with padding.
+pub static STANDARD: Engine = Engine::new(STANDARD_ALPHABET, true, None);
+
+/// Standard base64, without padding.
+pub static STANDARD_NO_PAD: Engine = Engine::new(STANDARD_ALPHABET, false, None);
+
+/// URL and filename safe base64, with padding.
+pub static URL_SAFE: Engine = Engine::new(URL_SAFE_ALPHABET, true, None);
+
+/// URL and filename safe base64, without padding.
+pub static URL_SAFE_NO_PAD: Engine = Engine::new(URL_SAFE_ALPHABET, false, None);
+
+/// MIME base64 (RFC 2045): standard base64 in lines of 76 characters
+/// separated by CRLF. Line breaks are skipped when decoding.
+pub static MIME: Engine = Engine::new(STANDARD_ALPHABET, true, Some(76));
+
+/// Encodes arbitrary bytes to a standard base64 string.
 pub fn encode(data: &[u8]) -> String {
-    let encoded = raw_encode(data);
-    let mut string = String::with_capacity(data.len() * (4 / 3));
-    for byte in encoded {
-        string.push(TABLE[byte as usize])
+    STANDARD.encode(data)
+}
+
+/// Decode an ASCII standard base64 string to bytes.
+pub fn decode(data: &str) -> Result<Vec<u8>> {
+    STANDARD.decode(data)
+}
+
+/// Why a base64 string could not be decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeErrorKind {
+    /// Not in the alphabet of the engine.
+    InvalidByte(u8),
+    /// Base64 can't be this long, or short.
+    InvalidLength,
+    /// A `=` where it should not be, or not one where it should.
+    InvalidPadding,
+    /// The last character has bits set which do not encode anything,
+    /// so the string is not the canonical encoding of any bytes.
+    TrailingBits,
+}
+
+/// An error decoding base64, with the position of the
+/// offending byte in the input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    pub position: usize,
+    pub kind: DecodeErrorKind,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            DecodeErrorKind::InvalidByte(b) => {
+                write!(f, "invalid byte 0x{:02x} at position {}", b, self.position)
+            }
+            DecodeErrorKind::InvalidLength => {
+                write!(f, "invalid length, ended at position {}", self.position)
+            }
+            DecodeErrorKind::InvalidPadding => {
+                write!(f, "invalid padding at position {}", self.position)
+            }
+            DecodeErrorKind::TrailingBits => {
+                write!(f, "trailing bits set at position {}", self.position)
+            }
+        }
     }
-    string
 }
 
-/// Encode arbitrary bytes and returns a vector
-/// with bytes. Each byte in the returning vector
-/// indicates the position of the encoding character.
-fn raw_encode(data: &[u8]) -> Vec<u8> {
-    let mut result = vec![];
-    for i in (0..data.len()).step_by(3) {
-        let first_byte = data.get(i);
-        let second_byte = data.get(i + 1);
-        let third_byte = data.get(i + 2);
+impl error::Error for DecodeError {}
+
+impl Engine {
+    const fn new(alphabet: &'static [u8; 64], pad: bool, line_length: Option<usize>) -> Self {
+        let mut decode_table = [INVALID; 256];
+        let mut i = 0;
+        while i < 64 {
+            decode_table[alphabet[i] as usize] = i as u8;
+            i += 1;
+        }
+        Self {
+            alphabet,
+            decode_table,
+            pad,
+            line_length,
+        }
+    }
+
+    /// The length of the base64 encoding of `len` bytes,
+    /// line breaks included.
+    pub fn encoded_len(&self, len: usize) -> usize {
+        let chars = match (len % 3, self.pad) {
+            (0, _) => len / 3 * 4,
+            (_, true) => len / 3 * 4 + 4,
+            (remainder, false) => len / 3 * 4 + remainder + 1,
+        };
+        match self.line_length {
+            Some(line_length) if chars > 0 => chars + (chars - 1) / line_length * 2,
+            _ => chars,
+        }
+    }
+
+    /// Encodes arbitrary bytes to a base64 string.
+    pub fn encode(&self, data: &[u8]) -> String {
+        let mut encoded = Vec::with_capacity(self.encoded_len(data.len()));
+        match self.line_length {
+            None => self.encode_unwrapped(data, &mut encoded),
+            Some(line_length) => {
+                let mut unwrapped = Vec::with_capacity(self.encoded_len(data.len()));
+                self.encode_unwrapped(data, &mut unwrapped);
+                for (i, line) in unwrapped.chunks(line_length).enumerate() {
+                    if i > 0 {
+                        encoded.extend_from_slice(b"\r\n");
+                    }
+                    encoded.extend_from_slice(line);
+                }
+            }
+        }
+        String::from_utf8(encoded).expect("base64 is always ASCII")
+    }
+
+    /// Encodes three bytes at a time straight through the alphabet,
+    /// then whatever is left, with padding if the engine wants it.
+    fn encode_unwrapped(&self, data: &[u8], encoded: &mut Vec<u8>) {
+        let table = self.alphabet;
+        let groups = data.chunks_exact(3);
+        let remainder = groups.remainder();
+        for group in groups {
+            let n = (group[0] as usize) << 16 | (group[1] as usize) << 8 | group[2] as usize;
+            encoded.extend_from_slice(&[
+                table[n >> 18],
+                table[n >> 12 & 0x3F],
+                table[n >> 6 & 0x3F],
+                table[n & 0x3F],
+            ]);
+        }
 
-        for byte in &encode_group(&[first_byte, second_byte, third_byte]) {
-            result.push(*byte)
+        match *remainder {
+            [first] => {
+                let n = (first as usize) << 16;
+                encoded.extend_from_slice(&[table[n >> 18], table[n >> 12 & 0x3F]]);
+                if self.pad {
+                    encoded.extend_from_slice(&[PAD, PAD]);
+                }
+            }
+            [first, second] => {
+                let n = (first as usize) << 16 | (second as usize) << 8;
+                encoded.extend_from_slice(&[
+                    table[n >> 18],
+                    table[n >> 12 & 0x3F],
+                    table[n >> 6 & 0x3F],
+                ]);
+                if self.pad {
+                    encoded.push(PAD);
+                }
+            }
+            _ => {}
         }
     }
 
-    result
+    /// Decode an ASCII base64 string to bytes.
+    /// Decoding is strict: everything not in the alphabet
+    /// (but line breaks for MIME), misplaced or missing padding
+    /// and non-canonical encodings are errors.
+    pub fn decode(&self, data: &str) -> Result<Vec<u8>> {
+        self.decode_bytes(data.as_bytes())
+    }
+
+    fn decode_bytes(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        let decoded = match self.line_length {
+            None => self.decode_unwrapped(bytes),
+            Some(_) => {
+                let unwrapped: Vec<u8> = bytes
+                    .iter()
+                    .copied()
+                    .filter(|&b| b != b'\r' && b != b'\n')
+                    .collect();
+                self.decode_unwrapped(&unwrapped)
+            }
+        };
+        // The fast path only says if something is wrong,
+        // go back and find out exactly what and where.
+        decoded.ok_or_else(|| self.diagnose(bytes))
+    }
+
+    /// Decodes four characters at a time through the decoding table.
+    /// Returns None on any error.
+    fn decode_unwrapped(&self, bytes: &[u8]) -> Option<Vec<u8>> {
+        let table = &self.decode_table;
+        let mut body = bytes;
+        if self.pad {
+            if !bytes.len().is_multiple_of(4) {
+                return None;
+            }
+            while let [rest @ .., PAD] = body {
+                body = rest;
+            }
+            if bytes.len() - body.len() > 2 {
+                return None;
+            }
+        }
+
+        let mut decoded = Vec::with_capacity(body.len() / 4 * 3 + 2);
+        let groups = body.chunks_exact(4);
+        let remainder = groups.remainder();
+        for group in groups {
+            let (a, b, c, d) = (
+                table[group[0] as usize],
+                table[group[1] as usize],
+                table[group[2] as usize],
+                table[group[3] as usize],
+            );
+            if (a | b | c | d) & INVALID == INVALID {
+                return None;
+            }
+            let n = (a as u32) << 18 | (b as u32) << 12 | (c as u32) << 6 | d as u32;
+            decoded.extend_from_slice(&[(n >> 16) as u8, (n >> 8) as u8, n as u8]);
+        }
+
+        let values: Vec<u8> = remainder.iter().map(|&b| table[b as usize]).collect();
+        match *values.as_slice() {
+            [] => {}
+            [a, b] if (a | b) & INVALID != INVALID && b & 0x0F == 0 => {
+                decoded.push(a << 2 | b >> 4);
+            }
+            [a, b, c] if (a | b | c) & INVALID != INVALID && c & 0x03 == 0 => {
+                decoded.extend_from_slice(&[a << 2 | b >> 4, b << 4 | c >> 2]);
+            }
+            _ => return None,
+        }
+        Some(decoded)
+    }
+
+    /// Finds the first error in a string the fast path refused.
+    fn diagnose(&self, bytes: &[u8]) -> DecodeError {
+        let error = |position, kind| DecodeError { position, kind };
+
+        // the base64 values of each byte together with their
+        // position in the input, line breaks skipped
+        let mut symbols = Vec::with_capacity(bytes.len());
+        for (position, &b) in bytes.iter().enumerate() {
+            if self.line_length.is_some() && (b == b'\r' || b == b'\n') {
+                continue;
+            }
+            let value = self.decode_table[b as usize];
+            if value == INVALID && (b != PAD || !self.pad) {
+                return error(position, DecodeErrorKind::InvalidByte(b));
+            }
+            symbols.push((position, b, value));
+        }
+
+        let first_pad = symbols.iter().position(|&(_, b, _)| b == PAD);
+        let values = &symbols[..first_pad.unwrap_or(symbols.len())];
+        if let Some(first_pad) = first_pad {
+            let pads = symbols.len() - first_pad;
+            if symbols[first_pad..].iter().any(|&(_, b, _)| b != PAD)
+                || pads > 2
+                || !symbols.len().is_multiple_of(4)
+            {
+                return error(symbols[first_pad].0, DecodeErrorKind::InvalidPadding);
+            }
+        } else if self.pad && !values.len().is_multiple_of(4) {
+            return error(bytes.len(), DecodeErrorKind::InvalidLength);
+        }
+
+        // a short last group is padded back, but the bits
+        // the padding replaces must not have anything in them
+        let &(position, _, last) = values.last().unwrap();
+        match values.len() % 4 {
+            1 => error(bytes.len(), DecodeErrorKind::InvalidLength),
+            2 if last & 0x0F != 0 => error(position, DecodeErrorKind::TrailingBits),
+            3 if last & 0x03 != 0 => error(position, DecodeErrorKind::TrailingBits),
+            _ => unreachable!("the fast path refused valid base64"),
+        }
+    }
 }
 
-/// Encodes a group of three bytes to a group of 4 base64 bytes.
-/// The first byte of the group must always be something.
-/// The other two bytes are optional, and data will  be padded
-/// accordingly.
-fn encode_group(bytes: &[Option<&u8>; 3]) -> [u8; 4] {
-    // Warning, here be dragons.
-    // base64 wants you to pad with zeroes, but only the byte needing it.
-    // after that, you should just ouput '='.
-    // that forces you to backtrack and check if the previous byte is present...
-    // even if you already padded it with zeroes.
-    let first = bytes[0].unwrap(); // the first one is always present.
-    let second = bytes[1].unwrap_or(&0);
-    let third = bytes[2].unwrap_or(&0);
-    let second_is_some = bytes[1].is_some();
-    let third_is_some = bytes[2].is_some();
-
-    // took me only two hours to came with the
-    // simplest mask idea.
-    // this comment is the only place i'll ever admit to it.
-    [
-        (first & 0b_1111_1100) >> 2,
-        (first & 0b_0000_0011) << 4 | (second & 0b_1111_0000) >> 4,
-        match second_is_some {
-            true => (second & 0b_0000_1111) << 2 | (third & 0b_1100_0000) >> 6,
-            _ => 64,
-        },
-        match third_is_some {
-            true => third & 0b_0011_1111,
-            _ => 64,
-        },
-    ]
+/// Encodes everything written to it as base64 into the inner writer.
+/// Call `finish` when done, or the last bytes will be written
+/// when dropped, with any error ignored.
+pub struct EncoderWriter<'e, W: Write> {
+    engine: &'e Engine,
+    inner: Option<W>,
+    // up to two bytes which did not make a whole group yet
+    leftover: Vec<u8>,
+    column: usize,
 }
 
-/// Decode an ASCII base64 string to bytes.
-pub fn decode(data: &str) -> Option<Vec<u8>> {
-    if data.len() % 4 != 0 {
-        return None;
+impl<'e, W: Write> EncoderWriter<'e, W> {
+    pub fn new(engine: &'e Engine, inner: W) -> Self {
+        Self {
+            engine,
+            inner: Some(inner),
+            leftover: Vec::with_capacity(3),
+            column: 0,
+        }
+    }
+
+    /// Writes the last group, padded, and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_leftover()?;
+        Ok(self.inner.take().unwrap())
+    }
+
+    fn write_leftover(&mut self) -> io::Result<()> {
+        let mut encoded = Vec::with_capacity(4);
+        self.engine.encode_unwrapped(&self.leftover, &mut encoded);
+        self.leftover.clear();
+        self.write_encoded(&encoded)
+    }
+
+    /// Writes encoded characters, breaking lines if needed.
+    fn write_encoded(&mut self, mut encoded: &[u8]) -> io::Result<()> {
+        let inner = self.inner.as_mut().unwrap();
+        let line_length = match self.engine.line_length {
+            None => return inner.write_all(encoded),
+            Some(line_length) => line_length,
+        };
+        while !encoded.is_empty() {
+            if self.column == line_length {
+                inner.write_all(b"\r\n")?;
+                self.column = 0;
+            }
+            let take = encoded.len().min(line_length - self.column);
+            inner.write_all(&encoded[..take])?;
+            self.column += take;
+            encoded = &encoded[take..];
+        }
+        Ok(())
     }
-    decode_raw(data.as_bytes())
 }
 
-/// Decode a group of raw bytes to a vector of bytes.
-fn decode_raw(bytes: &[u8]) -> Option<Vec<u8>> {
-    let non_ascii = match ascii_to_base64(bytes) {
-        Some(bytes) => bytes,
-        None => return None,
-    };
-    let mut decoded = Vec::with_capacity(bytes.len() * (3 / 4));
-    for i in (0..non_ascii.len()).step_by(4) {
-        let decoded_group = decode_group(&[
-            non_ascii[i],
-            non_ascii[i + 1],
-            non_ascii[i + 2],
-            non_ascii[i + 3],
-        ]);
-        let decoded_group: Vec<u8> = decoded_group
-            .iter()
-            .filter(|b| b.is_some())
-            .map(|b| b.unwrap())
-            .collect();
-        decoded.extend_from_slice(&decoded_group);
+impl<'e, W: Write> Write for EncoderWriter<'e, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut data = buf;
+        if !self.leftover.is_empty() {
+            let missing = (3 - self.leftover.len()).min(data.len());
+            self.leftover.extend_from_slice(&data[..missing]);
+            data = &data[missing..];
+            if self.leftover.len() < 3 {
+                return Ok(buf.len());
+            }
+            self.write_leftover()?;
+        }
+
+        let whole = data.len() / 3 * 3;
+        let mut encoded = Vec::with_capacity(whole / 3 * 4);
+        self.engine.encode_unwrapped(&data[..whole], &mut encoded);
+        self.write_encoded(&encoded)?;
+        self.leftover.extend_from_slice(&data[whole..]);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.as_mut().unwrap().flush()
     }
-    Some(decoded)
 }
 
-/// Return the base64 values of each byte in an ASCII-encoded
-/// byte slice.
-fn ascii_to_base64(bytes: &[u8]) -> Option<Vec<u8>> {
-    let mut non_ascii = Vec::with_capacity(bytes.len());
-    for b in bytes {
-        non_ascii.push(match *b as char {
-            'A'..='Z' => b - 65,
-            'a'..='z' => b - 71,
-            '0'..='9' => b + 4,
-            '+' => 62,
-            '/' => 63,
-            '=' => 64,
-            _ => return None,
-        });
+impl<'e, W: Write> Drop for EncoderWriter<'e, W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.write_leftover();
+        }
     }
-    Some(non_ascii)
 }
 
-/// Decode a group of 4 base64 bytes to 3 raw bytes.
-fn decode_group(bytes: &[u8; 4]) -> [Option<u8>; 3] {
-    let (first, second, third, fourth) = (bytes[0], bytes[1], bytes[2], bytes[3]);
-    [
-        Some((first & 0b_0011_1111) << 2 | (second & 0b_0011_0000) >> 4),
-        match third {
-            64 => None,
-            _ => Some((second & 0b_0000_1111) << 4 | (third & 0b_0011_1100) >> 2),
-        },
-        match fourth {
-            64 => None,
-            _ => Some((third & 0b_0000_0011) << 6 | fourth),
-        },
-    ]
+/// Decodes base64 read from the inner reader.
+/// Errors are `io::ErrorKind::InvalidData`, wrapping a `DecodeError`
+/// with the position in the whole stream.
+pub struct DecoderReader<'e, R: Read> {
+    engine: &'e Engine,
+    inner: R,
+    // encoded bytes read but not decoded yet
+    pending: Vec<u8>,
+    // position in the stream of the first pending byte
+    offset: usize,
+    decoded: Vec<u8>,
+    read: usize,
+    done: bool,
+}
+
+impl<'e, R: Read> DecoderReader<'e, R> {
+    pub fn new(engine: &'e Engine, inner: R) -> Self {
+        Self {
+            engine,
+            inner,
+            pending: Vec::new(),
+            offset: 0,
+            decoded: Vec::new(),
+            read: 0,
+            done: false,
+        }
+    }
+
+    /// Reads more from the inner reader and decodes all the groups
+    /// that can be decoded. The last group is only decoded at the
+    /// end of the stream, as only it may be short or padded.
+    fn fill(&mut self) -> io::Result<()> {
+        let mut buffer = [0; 4096];
+        let read = self.inner.read(&mut buffer)?;
+        self.pending.extend_from_slice(&buffer[..read]);
+        self.done = read == 0;
+
+        let cut = if self.done {
+            self.pending.len()
+        } else {
+            self.safe_cut()
+        };
+        if cut == 0 {
+            return Ok(());
+        }
+
+        if !self.done {
+            if let Some(position) = self.pending[..cut].iter().position(|&b| b == PAD) {
+                return Err(self.error(DecodeError {
+                    position,
+                    kind: DecodeErrorKind::InvalidPadding,
+                }));
+            }
+        }
+        self.decoded = self
+            .engine
+            .decode_bytes(&self.pending[..cut])
+            .map_err(|e| self.error(e))?;
+        self.read = 0;
+        self.pending.drain(..cut);
+        self.offset += cut;
+        Ok(())
+    }
+
+    /// Where to cut the pending bytes so all but the last group
+    /// are decoded.
+    fn safe_cut(&self) -> usize {
+        let mut symbols = 0;
+        let mut cut = 0;
+        let total = self
+            .pending
+            .iter()
+            .filter(|&&b| !self.is_line_break(b))
+            .count();
+        let decodable = total.saturating_sub(1) / 4 * 4;
+        for (i, &b) in self.pending.iter().enumerate() {
+            if symbols == decodable {
+                break;
+            }
+            if !self.is_line_break(b) {
+                symbols += 1;
+                cut = i + 1;
+            }
+        }
+        cut
+    }
+
+    fn is_line_break(&self, b: u8) -> bool {
+        self.engine.line_length.is_some() && (b == b'\r' || b == b'\n')
+    }
+
+    fn error(&self, mut error: DecodeError) -> io::Error {
+        error.position += self.offset;
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+impl<'e, R: Read> Read for DecoderReader<'e, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.read == self.decoded.len() && !self.done {
+            self.fill()?;
+        }
+        let available = &self.decoded[self.read..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.read += n;
+        Ok(n)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::encoding::hex;
+    use std::io::{Read, Write};
     use std::str;
 
     #[test]
@@ -167,6 +537,142 @@ mod tests {
         assert_eq!(&encode(&input), "SSdt");
     }
 
+    #[test]
+    fn test_rfc4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (plain, encoded) in vectors.iter() {
+            assert_eq!(&encode(plain.as_bytes()), encoded);
+            assert_eq!(decode(encoded).unwrap(), plain.as_bytes());
+            let unpadded = encoded.trim_end_matches('=');
+            assert_eq!(STANDARD_NO_PAD.encode(plain.as_bytes()), unpadded);
+            assert_eq!(STANDARD_NO_PAD.decode(unpadded).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_url_safe() {
+        let input = [0xfb, 0xff];
+        assert_eq!(&encode(&input), "+/8=");
+        assert_eq!(&URL_SAFE.encode(&input), "-_8=");
+        assert_eq!(&URL_SAFE_NO_PAD.encode(&input), "-_8");
+        assert_eq!(URL_SAFE.decode("-_8=").unwrap(), input);
+        assert_eq!(
+            decode("-_8=").unwrap_err(),
+            DecodeError {
+                position: 0,
+                kind: DecodeErrorKind::InvalidByte(b'-')
+            }
+        );
+    }
+
+    #[test]
+    fn test_mime() {
+        let input = [0u8; 60];
+        let encoded = MIME.encode(&input);
+        let lines: Vec<&str> = encoded.split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 76);
+        assert_eq!(lines[1].len(), 4);
+        assert_eq!(MIME.decode(&encoded).unwrap(), input.to_vec());
+        assert!(decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let error = |position, kind| Err(DecodeError { position, kind });
+        assert_eq!(decode("aG9sYQ="), error(6, DecodeErrorKind::InvalidPadding));
+        assert_eq!(decode("aG9sY"), error(5, DecodeErrorKind::InvalidLength));
+        assert_eq!(
+            decode("aG=sYQ=="),
+            error(2, DecodeErrorKind::InvalidPadding)
+        );
+        assert_eq!(
+            decode("aG9s===="),
+            error(4, DecodeErrorKind::InvalidPadding)
+        );
+        assert_eq!(decode("aG9sYR=="), error(5, DecodeErrorKind::TrailingBits));
+        assert_eq!(
+            decode("aG9s YQ=="),
+            error(4, DecodeErrorKind::InvalidByte(b' '))
+        );
+        assert_eq!(
+            STANDARD_NO_PAD.decode("aG9sYQ=="),
+            error(6, DecodeErrorKind::InvalidByte(b'='))
+        );
+    }
+
+    #[test]
+    fn test_encoded_len() {
+        for len in 0..200 {
+            let data = vec![0xAB; len];
+            for engine in [&STANDARD, &STANDARD_NO_PAD, &MIME].iter() {
+                assert_eq!(engine.encode(&data).len(), engine.encoded_len(len));
+            }
+        }
+    }
+
+    #[test]
+    fn test_encoder_writer() {
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        for engine in [&STANDARD, &URL_SAFE_NO_PAD, &MIME].iter() {
+            let mut writer = EncoderWriter::new(engine, Vec::new());
+            for chunk in data.chunks(7) {
+                writer.write_all(chunk).unwrap();
+            }
+            let encoded = writer.finish().unwrap();
+            assert_eq!(str::from_utf8(&encoded).unwrap(), engine.encode(&data));
+        }
+    }
+
+    #[test]
+    fn test_decoder_reader() {
+        let data: Vec<u8> = (0..=255).cycle().take(10_000).collect();
+        for engine in [&STANDARD, &URL_SAFE_NO_PAD, &MIME].iter() {
+            let encoded = engine.encode(&data);
+            let mut decoded = Vec::new();
+            DecoderReader::new(engine, encoded.as_bytes())
+                .read_to_end(&mut decoded)
+                .unwrap();
+            assert_eq!(decoded, data);
+        }
+    }
+
+    #[test]
+    fn test_decoder_reader_errors() {
+        let mut encoded = encode(&[0; 6000]);
+        encoded.replace_range(5000..5001, "*");
+        let mut decoded = Vec::new();
+        let error = DecoderReader::new(&STANDARD, encoded.as_bytes())
+            .read_to_end(&mut decoded)
+            .unwrap_err();
+        let error = error
+            .into_inner()
+            .unwrap()
+            .downcast::<DecodeError>()
+            .unwrap();
+        assert_eq!(
+            *error,
+            DecodeError {
+                position: 5000,
+                kind: DecodeErrorKind::InvalidByte(b'*')
+            }
+        );
+
+        let mut decoded = Vec::new();
+        let concatenated = "Zg==".repeat(2000);
+        assert!(DecoderReader::new(&STANDARD, concatenated.as_bytes())
+            .read_to_end(&mut decoded)
+            .is_err());
+    }
+
     #[test]
     fn test_the_real_thing() {
         let input = hex::from_string(
@@ -0,0 +1,246 @@
+use std::{error, fmt};
+
+type Result<T> = std::result::Result<T, DecodeError>;
+
+/// The Z85 alphabet, from the ZeroMQ RFC 32.
+/// Unlike Ascii85, it's safe to put in strings and source code.
+static Z85_ALPHABET: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+/// Why a base85 string could not be decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeErrorKind {
+    /// Not in the alphabet, or a `z` in the middle of a group.
+    InvalidByte(u8),
+    /// A single character left at the end, or not a multiple of
+    /// five characters for Z85.
+    InvalidLength,
+    /// The group is bigger than what fits in four bytes.
+    Overflow,
+}
+
+/// An error decoding base85, with the position of the
+/// offending byte in the input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    pub position: usize,
+    pub kind: DecodeErrorKind,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            DecodeErrorKind::InvalidByte(b) => {
+                write!(f, "invalid byte 0x{:02x} at position {}", b, self.position)
+            }
+            DecodeErrorKind::InvalidLength => {
+                write!(f, "invalid length, ended at position {}", self.position)
+            }
+            DecodeErrorKind::Overflow => {
+                write!(f, "group overflows at position {}", self.position)
+            }
+        }
+    }
+}
+
+impl error::Error for DecodeError {}
+
+/// Encodes bytes to Ascii85, as used by PostScript and PDF,
+/// without the `<~ ~>` delimiters.
+/// Groups of four zeroes are shortened to `z`.
+pub fn encode_ascii85(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(4) * 5);
+    for chunk in data.chunks(4) {
+        let mut group = [0; 4];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let value = u32::from_be_bytes(group);
+        if value == 0 && chunk.len() == 4 {
+            encoded.push('z');
+            continue;
+        }
+        // a partial group only needs one more character than it has bytes
+        for &digit in &digits(value)[..chunk.len() + 1] {
+            encoded.push((b'!' + digit) as char);
+        }
+    }
+    encoded
+}
+
+/// Decode Ascii85 to bytes.
+/// The `<~ ~>` delimiters are optional and whitespace is skipped.
+pub fn decode_ascii85(data: &str) -> Result<Vec<u8>> {
+    let mut bytes = data.as_bytes();
+    let mut offset = 0;
+    if bytes.starts_with(b"<~") {
+        bytes = &bytes[2..];
+        offset = 2;
+    }
+    if bytes.ends_with(b"~>") {
+        bytes = &bytes[..bytes.len() - 2];
+    }
+
+    let mut decoded = Vec::with_capacity(bytes.len() * 4 / 5);
+    let mut group = [0; 5];
+    let mut len = 0;
+    let mut last = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        let position = offset + i;
+        match b {
+            b if b.is_ascii_whitespace() => continue,
+            b'z' if len == 0 => decoded.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                group[len] = b - b'!';
+                len += 1;
+                if len == 5 {
+                    decoded.extend_from_slice(&from_digits(&group, position)?);
+                    len = 0;
+                }
+            }
+            _ => {
+                return Err(DecodeError {
+                    position,
+                    kind: DecodeErrorKind::InvalidByte(b),
+                })
+            }
+        }
+        last = position;
+    }
+
+    if len == 1 {
+        return Err(DecodeError {
+            position: last + 1,
+            kind: DecodeErrorKind::InvalidLength,
+        });
+    }
+    if len > 1 {
+        // pad with the highest digit, so the truncation rounds back
+        for digit in group.iter_mut().skip(len) {
+            *digit = 84;
+        }
+        decoded.extend_from_slice(&from_digits(&group, last)?[..len - 1]);
+    }
+    Ok(decoded)
+}
+
+/// Encodes bytes to Z85, the ZeroMQ flavour of base85.
+/// The length of the data must be a multiple of four.
+pub fn encode_z85(data: &[u8]) -> Option<String> {
+    if !data.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut encoded = String::with_capacity(data.len() / 4 * 5);
+    for chunk in data.chunks(4) {
+        let value = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        for &digit in digits(value).iter() {
+            encoded.push(Z85_ALPHABET[digit as usize] as char);
+        }
+    }
+    Some(encoded)
+}
+
+/// Decode Z85 to bytes.
+/// The length must be a multiple of five.
+pub fn decode_z85(data: &str) -> Result<Vec<u8>> {
+    let bytes = data.as_bytes();
+    if !bytes.len().is_multiple_of(5) {
+        return Err(DecodeError {
+            position: bytes.len(),
+            kind: DecodeErrorKind::InvalidLength,
+        });
+    }
+
+    let mut decoded = Vec::with_capacity(bytes.len() / 5 * 4);
+    for (i, chunk) in bytes.chunks(5).enumerate() {
+        let mut group = [0; 5];
+        for (j, &b) in chunk.iter().enumerate() {
+            group[j] = match Z85_ALPHABET.iter().position(|&a| a == b) {
+                Some(value) => value as u8,
+                None => {
+                    return Err(DecodeError {
+                        position: i * 5 + j,
+                        kind: DecodeErrorKind::InvalidByte(b),
+                    })
+                }
+            };
+        }
+        decoded.extend_from_slice(&from_digits(&group, i * 5 + 4)?);
+    }
+    Ok(decoded)
+}
+
+/// The five base 85 digits of a number, most significant first.
+fn digits(mut value: u32) -> [u8; 5] {
+    let mut digits = [0; 5];
+    for digit in digits.iter_mut().rev() {
+        *digit = (value % 85) as u8;
+        value /= 85;
+    }
+    digits
+}
+
+/// The four bytes of five base 85 digits, most significant first.
+/// `position` is where the group ends, for the error.
+fn from_digits(digits: &[u8; 5], position: usize) -> Result<[u8; 4]> {
+    let value = digits
+        .iter()
+        .fold(0u64, |value, &digit| value * 85 + digit as u64);
+    if value > u32::MAX as u64 {
+        return Err(DecodeError {
+            position,
+            kind: DecodeErrorKind::Overflow,
+        });
+    }
+    Ok((value as u32).to_be_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::hex;
+
+    #[test]
+    fn test_ascii85() {
+        let vectors = [
+            ("", ""),
+            ("Man ", "9jqo^"),
+            ("Man is distinguished", "9jqo^BlbD-BleB1DJ+*+F(f,q"),
+            ("sure.", "F*2M7/c"),
+            ("\0\0\0\0\0", "z!!"),
+        ];
+        for (plain, encoded) in vectors.iter() {
+            assert_eq!(&encode_ascii85(plain.as_bytes()), encoded);
+            assert_eq!(decode_ascii85(encoded).unwrap(), plain.as_bytes());
+        }
+        assert_eq!(decode_ascii85("<~9jqo^\n F*2M7/c~>").unwrap(), b"Man sure.");
+    }
+
+    #[test]
+    fn test_ascii85_errors() {
+        let error = |position, kind| Err(DecodeError { position, kind });
+        assert_eq!(
+            decode_ascii85("9jqo^B"),
+            error(6, DecodeErrorKind::InvalidLength)
+        );
+        assert_eq!(
+            decode_ascii85("9jzo^"),
+            error(2, DecodeErrorKind::InvalidByte(b'z'))
+        );
+        assert_eq!(decode_ascii85("uuuuu"), error(4, DecodeErrorKind::Overflow));
+    }
+
+    #[test]
+    fn test_z85() {
+        let data = hex::from_string("864FD26FB559F75B").unwrap();
+        assert_eq!(encode_z85(&data).unwrap(), "HelloWorld");
+        assert_eq!(decode_z85("HelloWorld").unwrap(), data);
+        assert_eq!(encode_z85(b"odd"), None);
+        assert_eq!(decode_z85("Hello"), Ok(vec![0x86, 0x4F, 0xD2, 0x6F]));
+        assert_eq!(
+            decode_z85("Hell"),
+            Err(DecodeError {
+                position: 4,
+                kind: DecodeErrorKind::InvalidLength
+            })
+        );
+    }
+}
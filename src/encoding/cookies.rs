@@ -1,3 +1,4 @@
+use crate::encoding::urlencoded;
 use std::collections::BTreeMap;
 use std::fmt;
 
@@ -16,51 +17,22 @@ impl fmt::Display for CookieError {
     }
 }
 
+/// Decode a form encoded cookie to a map.
+/// If a key is repeated the last value wins; use
+/// `urlencoded::decode` to see all of them.
 pub fn decode(data: &str) -> Result<BTreeMap<String, String>> {
-    let mut result = BTreeMap::new();
-
-    let attrs = data.split('&').map(|a| a.split('='));
-    for mut attr in attrs {
-        let key = match attr.next() {
-            Some("") => continue,
-            Some(s) => s.to_owned(),
-            // It is surprisingly impossible as far as I can tell
-            // to get a zero-length iterator from a split.
-            None => unreachable!(),
-        };
-        let value = attr.next().map(|s| s.into()).unwrap_or(String::from(""));
-
-        if let Some(_) = attr.next() {
-            return Result::Err(CookieError::MalformedCookie {
-                cookie: data.into(),
-            });
-        }
-
-        result.insert(key, value);
-    }
-
-    Result::Ok(result)
+    let params = urlencoded::decode(data).map_err(|_| CookieError::MalformedCookie {
+        cookie: data.into(),
+    })?;
+    Ok(params
+        .iter()
+        .map(|(k, v)| (k.to_owned(), v.to_owned()))
+        .collect())
 }
 
+/// Encodes a map to a form encoded cookie, escaping it.
 pub fn encode(map: BTreeMap<&str, String>) -> String {
-    let mut result = String::new();
-    if map.is_empty() {
-        return result;
-    }
-
-    let mut iter = map.into_iter();
-
-    let (key, value) = iter.next().unwrap();
-    result += &key;
-    result += "=";
-    result += &value;
-    for (key, value) in iter {
-        result += "&";
-        result += &key;
-        result += "=";
-        result += &value;
-    }
-    result
+    urlencoded::encode(map)
 }
 
 #[cfg(test)]
@@ -82,4 +54,13 @@ mod test {
         let expected = BTreeMap::new();
         assert_eq!(map, expected);
     }
+
+    #[test]
+    fn test_encode_escapes() {
+        let mut map = BTreeMap::new();
+        map.insert("email", String::from("foo@bar.com&role=admin"));
+        let cookie = encode(map);
+        assert_eq!(cookie, "email=foo%40bar.com%26role%3Dadmin");
+        assert_eq!(decode(&cookie).unwrap()["email"], "foo@bar.com&role=admin");
+    }
 }
@@ -1,18 +1,193 @@
-/// Convert an array of bytes to a hex string.
+use std::fmt::Write;
+use std::{error, fmt};
+
+type Result<T> = std::result::Result<T, DecodeError>;
+
+/// The case of the letters in a hex string.
+#[derive(Clone, Copy, Debug)]
+pub enum Case {
+    Lower,
+    Upper,
+}
+
+/// Convert an array of bytes to an uppercase hex string.
 pub fn to_string(src: &[u8]) -> String {
-    src.iter().map(|b| format!("{:02X}", b)).collect()
+    encode(src, Case::Upper)
+}
+
+/// Convert an array of bytes to a hex string.
+pub fn encode(src: &[u8], case: Case) -> String {
+    let mut hex = String::with_capacity(src.len() * 2);
+    for b in src {
+        match case {
+            Case::Lower => write!(hex, "{:02x}", b),
+            Case::Upper => write!(hex, "{:02X}", b),
+        }
+        .expect("writing to a string can't fail");
+    }
+    hex
 }
 
+/// Why a hex string could not be decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeErrorKind {
+    /// Not a hex digit nor a separator.
+    InvalidChar(char),
+    /// A separator or the end of the string split a byte in half.
+    OddLength,
+}
+
+/// An error decoding hex, with the position of the
+/// offending character in the input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    pub position: usize,
+    pub kind: DecodeErrorKind,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            DecodeErrorKind::InvalidChar(c) => {
+                write!(f, "invalid character {:?} at position {}", c, self.position)
+            }
+            DecodeErrorKind::OddLength => write!(f, "half a byte at position {}", self.position),
+        }
+    }
+}
+
+impl error::Error for DecodeError {}
+
 /// Convert a hex string to a vector of bytes.
-pub fn from_string(src: &str) -> Option<Vec<u8>> {
-    let chars: Vec<char> = src.chars().filter(|c| !c.is_whitespace()).collect();
-    let mut vec = Vec::new();
-    for (i, _) in chars.iter().enumerate().step_by(2) {
-        let a = chars[i];
-        let b = chars[i + 1];
-        let hex_u8 = format!("{}{}", a, b);
-        let number = u8::from_str_radix(&hex_u8, 16).unwrap();
-        vec.push(number);
-    }
-    Some(vec)
+/// Either case is fine, and so are whitespace or `:` between
+/// bytes and a `0x` in front of any group of them, so all of
+/// "0a0b", "0A 0B", "0a:0b" and "0x0a 0x0b" decode to [10, 11].
+pub fn from_string(src: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(src.len() / 2);
+    // the first half of a byte, and where it was
+    let mut high: Option<(usize, u8)> = None;
+    let mut chars = src.char_indices().peekable();
+    let mut token_start = true;
+
+    while let Some((position, c)) = chars.next() {
+        if c.is_whitespace() || c == ':' {
+            if let Some((position, _)) = high {
+                return Err(DecodeError {
+                    position,
+                    kind: DecodeErrorKind::OddLength,
+                });
+            }
+            token_start = true;
+            continue;
+        }
+
+        if token_start && c == '0' {
+            if let Some(&(_, 'x')) | Some(&(_, 'X')) = chars.peek() {
+                chars.next();
+                token_start = false;
+                continue;
+            }
+        }
+        token_start = false;
+
+        let nibble = match c.to_digit(16) {
+            Some(nibble) => nibble as u8,
+            None => {
+                return Err(DecodeError {
+                    position,
+                    kind: DecodeErrorKind::InvalidChar(c),
+                })
+            }
+        };
+        match high.take() {
+            Some((_, high)) => bytes.push(high << 4 | nibble),
+            None => high = Some((position, nibble)),
+        }
+    }
+
+    if let Some((position, _)) = high {
+        return Err(DecodeError {
+            position,
+            kind: DecodeErrorKind::OddLength,
+        });
+    }
+    Ok(bytes)
+}
+
+/// Formats bytes like `hexdump -C` does: offset, sixteen
+/// bytes in hex and the same bytes as ASCII, dots for
+/// the unprintable ones.
+pub fn dump(src: &[u8]) -> String {
+    let mut dump = String::new();
+    for (line, chunk) in src.chunks(16).enumerate() {
+        write!(dump, "{:08x}  ", line * 16).unwrap();
+        for i in 0..16 {
+            match chunk.get(i) {
+                Some(b) => write!(dump, "{:02x} ", b).unwrap(),
+                None => dump.push_str("   "),
+            }
+            if i == 7 {
+                dump.push(' ');
+            }
+        }
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| match b {
+                0x20..=0x7e => b as char,
+                _ => '.',
+            })
+            .collect();
+        writeln!(dump, " |{}|", ascii).unwrap();
+    }
+    writeln!(dump, "{:08x}", src.len()).unwrap();
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let bytes = [0x00, 0xab, 0x1f];
+        assert_eq!(to_string(&bytes), "00AB1F");
+        assert_eq!(encode(&bytes, Case::Lower), "00ab1f");
+    }
+
+    #[test]
+    fn test_from_string() {
+        let expected = Ok(vec![0x0a, 0x0b, 0xff]);
+        assert_eq!(from_string("0a0bff"), expected);
+        assert_eq!(from_string("0A0BFF"), expected);
+        assert_eq!(from_string("0a 0b\n ff"), expected);
+        assert_eq!(from_string("0a:0b:ff"), expected);
+        assert_eq!(from_string("0x0a0bff"), expected);
+        assert_eq!(from_string("0x0a 0x0b 0xff"), expected);
+        assert_eq!(from_string(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_from_string_errors() {
+        let error = |position, kind| Err(DecodeError { position, kind });
+        assert_eq!(from_string("0a0"), error(2, DecodeErrorKind::OddLength));
+        assert_eq!(from_string("0a0 b"), error(2, DecodeErrorKind::OddLength));
+        assert_eq!(
+            from_string("0a0g"),
+            error(3, DecodeErrorKind::InvalidChar('g'))
+        );
+        assert_eq!(
+            from_string("0a0x0b"),
+            error(3, DecodeErrorKind::InvalidChar('x'))
+        );
+    }
+
+    #[test]
+    fn test_dump() {
+        let expected = "\
+00000000  49 27 6d 20 6b 69 6c 6c  69 6e 67 20 79 6f 75 72  |I'm killing your|
+00000010  20 62 72 61 69 6e 0a 00                           | brain..|
+00000018
+";
+        assert_eq!(dump(b"I'm killing your brain\n\0"), expected);
+    }
 }
@@ -1,3 +1,8 @@
+pub mod base32;
+pub mod base58;
 pub mod base64;
+pub mod base85;
 pub mod cookies;
 pub mod hex;
+pub mod pem;
+pub mod urlencoded;
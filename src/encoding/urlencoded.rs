@@ -0,0 +1,281 @@
+use std::{error, fmt, str};
+
+type Result<T> = std::result::Result<T, DecodeError>;
+
+/// A flavour of URL encoded key-value pairs:
+/// what separates the pairs and how spaces are written.
+pub struct Codec {
+    separator: char,
+    space_as_plus: bool,
+}
+
+/// `application/x-www-form-urlencoded`, as in query strings and
+/// form bodies: `&` separated and spaces as `+`.
+pub static FORM: Codec = Codec {
+    separator: '&',
+    space_as_plus: true,
+};
+
+/// `;` separated pairs, spaces as `%20`, like
+/// `comment1=cooking%20MCs;userdata=foo`.
+pub static SEMICOLON: Codec = Codec {
+    separator: ';',
+    space_as_plus: false,
+};
+
+/// Why a URL encoded string could not be decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeErrorKind {
+    /// A `%` not followed by two hex digits.
+    InvalidPercent,
+    /// The unescaped bytes are not UTF-8.
+    InvalidUtf8,
+}
+
+/// An error decoding a URL encoded string, with the position
+/// of the offending byte in the input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    pub position: usize,
+    pub kind: DecodeErrorKind,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            DecodeErrorKind::InvalidPercent => {
+                write!(f, "invalid percent escape at position {}", self.position)
+            }
+            DecodeErrorKind::InvalidUtf8 => {
+                write!(f, "invalid UTF-8 at position {}", self.position)
+            }
+        }
+    }
+}
+
+impl error::Error for DecodeError {}
+
+/// Ordered key-value pairs, where keys can be repeated.
+/// Unlike a map, `a=1&b=2&a=3` survives a round trip.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Params {
+    pairs: Vec<(String, String)>,
+}
+
+impl Params {
+    pub fn new() -> Self {
+        Params { pairs: Vec::new() }
+    }
+
+    /// Adds a pair at the end, even if the key is already there.
+    pub fn append(&mut self, key: &str, value: &str) {
+        self.pairs.push((key.to_string(), value.to_string()));
+    }
+
+    /// The value of the first pair with this key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    /// The values of all the pairs with this key, in order.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.iter().filter(move |(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+impl<K: AsRef<str>, V: AsRef<str>> std::iter::FromIterator<(K, V)> for Params {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut params = Params::new();
+        for (key, value) in iter {
+            params.append(key.as_ref(), value.as_ref());
+        }
+        params
+    }
+}
+
+/// Encodes pairs to a standard form encoded string.
+pub fn encode<I, K, V>(pairs: I) -> String
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    FORM.encode(pairs)
+}
+
+/// Decode a standard form encoded string.
+pub fn decode(data: &str) -> Result<Params> {
+    FORM.decode(data)
+}
+
+impl Codec {
+    /// Encodes pairs, escaping both keys and values,
+    /// so neither can smuggle in a separator or a `=`.
+    pub fn encode<I, K, V>(&self, pairs: I) -> String
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut encoded = String::new();
+        for (i, (key, value)) in pairs.into_iter().enumerate() {
+            if i > 0 {
+                encoded.push(self.separator);
+            }
+            encoded.push_str(&self.escape(key.as_ref()));
+            encoded.push('=');
+            encoded.push_str(&self.escape(value.as_ref()));
+        }
+        encoded
+    }
+
+    /// Decode pairs. Keys and values are split at the first `=`,
+    /// so values can have more of them, and empty pairs are skipped.
+    pub fn decode(&self, data: &str) -> Result<Params> {
+        let mut params = Params::new();
+        let mut offset = 0;
+        for pair in data.split(self.separator) {
+            let start = offset;
+            offset += pair.len() + self.separator.len_utf8();
+            if pair.is_empty() {
+                continue;
+            }
+            let (key, value) = match pair.find('=') {
+                Some(i) => (&pair[..i], &pair[i + 1..]),
+                None => (pair, ""),
+            };
+            let key = self.unescape_at(key, start)?;
+            let value = self.unescape_at(value, start + pair.len() - value.len())?;
+            params.pairs.push((key, value));
+        }
+        Ok(params)
+    }
+
+    /// Percent-encodes everything but ASCII alphanumerics and `*-._`,
+    /// as the WHATWG URL standard does for forms.
+    pub fn escape(&self, data: &str) -> String {
+        let mut escaped = String::with_capacity(data.len());
+        for &b in data.as_bytes() {
+            match b {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => {
+                    escaped.push(b as char)
+                }
+                b' ' if self.space_as_plus => escaped.push('+'),
+                _ => escaped.push_str(&format!("%{:02X}", b)),
+            }
+        }
+        escaped
+    }
+
+    /// Reverses `escape`, accepting either case of hex digits.
+    pub fn unescape(&self, data: &str) -> Result<String> {
+        self.unescape_at(data, 0)
+    }
+
+    // `offset` is where data starts in the whole input, for the errors.
+    fn unescape_at(&self, data: &str, offset: usize) -> Result<String> {
+        let bytes = data.as_bytes();
+        let mut unescaped = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'%' => {
+                    let byte = bytes
+                        .get(i + 1..i + 3)
+                        .and_then(|hex| str::from_utf8(hex).ok())
+                        .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                        .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                    match byte {
+                        Some(byte) => unescaped.push(byte),
+                        None => {
+                            return Err(DecodeError {
+                                position: offset + i,
+                                kind: DecodeErrorKind::InvalidPercent,
+                            })
+                        }
+                    }
+                    i += 3;
+                    continue;
+                }
+                b'+' if self.space_as_plus => unescaped.push(b' '),
+                b => unescaped.push(b),
+            }
+            i += 1;
+        }
+        String::from_utf8(unescaped).map_err(|e| DecodeError {
+            // the position in the unescaped bytes is only a lower bound
+            position: offset + e.utf8_error().valid_up_to(),
+            kind: DecodeErrorKind::InvalidUtf8,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_escapes() {
+        let encoded = encode(vec![("email", "foo@bar.com&role=admin"), ("a b", "c+d")]);
+        assert_eq!(encoded, "email=foo%40bar.com%26role%3Dadmin&a+b=c%2Bd");
+        let params = decode(&encoded).unwrap();
+        assert_eq!(params.get("email"), Some("foo@bar.com&role=admin"));
+        assert_eq!(params.get("a b"), Some("c+d"));
+        assert_eq!(params.get("role"), None);
+    }
+
+    #[test]
+    fn test_duplicates_keep_order() {
+        let params = decode("a=1&b=2&a=3&&c").unwrap();
+        assert_eq!(params.len(), 4);
+        assert_eq!(params.get("a"), Some("1"));
+        assert_eq!(params.get_all("a").collect::<Vec<_>>(), vec!["1", "3"]);
+        assert_eq!(params.get("c"), Some(""));
+        assert_eq!(encode(params.iter()), "a=1&b=2&a=3&c=");
+    }
+
+    #[test]
+    fn test_value_with_equals() {
+        let params = decode("token=YWJj==&x=%e2%82%ac").unwrap();
+        assert_eq!(params.get("token"), Some("YWJj=="));
+        assert_eq!(params.get("x"), Some("€"));
+    }
+
+    #[test]
+    fn test_semicolon() {
+        let encoded = SEMICOLON.encode(vec![
+            ("comment1", "cooking MCs"),
+            ("userdata", ";admin=true"),
+        ]);
+        assert_eq!(encoded, "comment1=cooking%20MCs;userdata=%3Badmin%3Dtrue");
+        let params = SEMICOLON.decode("a=1+1;b=2%20").unwrap();
+        assert_eq!(params.get("a"), Some("1+1"));
+        assert_eq!(params.get("b"), Some("2 "));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let error = |position, kind| Err(DecodeError { position, kind });
+        assert_eq!(
+            decode("a=1&b=%2"),
+            error(6, DecodeErrorKind::InvalidPercent)
+        );
+        assert_eq!(
+            decode("a=1&b%zz=2"),
+            error(5, DecodeErrorKind::InvalidPercent)
+        );
+        assert_eq!(decode("a=%ff"), error(2, DecodeErrorKind::InvalidUtf8));
+    }
+}
@@ -16,18 +16,13 @@ pub fn xor_cypher() {
     let cypher_text = "1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736";
     let encrypted_as_hex = hex::from_string(&cypher_text).unwrap();
 
-    // All ASCII characters can fill 7 bits: from zero to 127.
-    for i in 0..127 {
-        let xored = bytes::repeating_xor(&encrypted_as_hex, &[i as u8]);
-        let text = match bytes::to_string(&xored) {
-            Some(text) => text,
-            None => continue,
-        };
-        let score = frequency::analysis(&text);
-        if score > 0.6 {
-            println!("{}, key: {}", text, i);
-        }
-    }
+    // Try every key and keep the one that looks the most like English.
+    let english = frequency::english();
+    let (key, text) = (0..=255)
+        .map(|i| (i, bytes::repeating_xor(&encrypted_as_hex, &[i])))
+        .max_by(|(_, a), (_, b)| english.score(a).total_cmp(&english.score(b)))
+        .unwrap();
+    println!("{}, key: {}", String::from_utf8_lossy(&text), key);
 }
 
 #[allow(dead_code)]
@@ -39,20 +34,25 @@ pub fn xor_file() {
         Err(_) => panic!("file not found!"),
     };
     let buffer = BufReader::new(file);
+    let english = frequency::english();
+    let mut best: Option<(f64, Vec<u8>, u8)> = None;
     for encrypted_line in buffer.lines().map(|l| l.unwrap()) {
         let encrypted_bytes = hex::from_string(&encrypted_line).unwrap();
-        for i in 0..127 {
-            let xored = bytes::repeating_xor(&encrypted_bytes, &[i as u8]);
-            let text = match bytes::to_string(&xored) {
-                Some(text) => text,
-                None => continue, // most probably our secret is utf8, at least?
-            };
-            let score = frequency::analysis(&text);
-            if score > 0.7 {
-                println!("score: {}, text: {}, key: {}", score, text, i);
+        for i in 0..=255 {
+            let xored = bytes::repeating_xor(&encrypted_bytes, &[i]);
+            let score = english.score(&xored);
+            if best.as_ref().is_none_or(|(best, _, _)| score > *best) {
+                best = Some((score, xored, i));
             }
         }
     }
+    let (score, text, key) = best.expect("no lines in file");
+    println!(
+        "score: {}, text: {}, key: {}",
+        score,
+        String::from_utf8_lossy(&text),
+        key
+    );
 }
 
 #[allow(dead_code)]
@@ -0,0 +1,54 @@
+use super::histogram;
+
+/// The Shannon entropy of the bytes, in bits per byte:
+/// 0 if they are all the same, 8 if all of them are equally common.
+/// A text is around 4 to 5, compressed or encrypted data near 8.
+pub fn entropy(bytes: &[u8]) -> f64 {
+    let len = bytes.len() as f64;
+    histogram(bytes)
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            p * (1.0 / p).log2()
+        })
+        .sum()
+}
+
+/// The entropy of every `window` bytes, moving `step` bytes at a time,
+/// to find the encrypted or compressed parts of a bigger blob.
+/// Empty if there are less than `window` bytes, or if `window` or `step` is 0.
+pub fn sliding_window_entropy(bytes: &[u8], window: usize, step: usize) -> Vec<f64> {
+    if window == 0 || step == 0 {
+        return Vec::new();
+    }
+    bytes.windows(window).step_by(step).map(entropy).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entropy() {
+        assert_eq!(entropy(b""), 0.0);
+        assert_eq!(entropy(b"aaaa"), 0.0);
+        assert_eq!(entropy(b"abab"), 1.0);
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(entropy(&all), 8.0);
+    }
+
+    #[test]
+    fn test_sliding_window_entropy() {
+        let mut bytes = vec![0; 256];
+        bytes.extend(0..=255);
+        let entropies = sliding_window_entropy(&bytes, 256, 128);
+        assert_eq!(entropies.len(), 3);
+        assert_eq!(entropies[0], 0.0);
+        assert!(entropies[1] > 4.0 && entropies[1] < 5.0);
+        assert_eq!(entropies[2], 8.0);
+        assert!(sliding_window_entropy(b"short", 256, 1).is_empty());
+        assert!(sliding_window_entropy(&bytes, 0, 1).is_empty());
+        assert!(sliding_window_entropy(&bytes, 256, 0).is_empty());
+    }
+}
@@ -1,7 +1,79 @@
-use std::collections::{BTreeMap, HashMap};
-use std::iter::FromIterator;
+//! Frequency analysis: letter scores, byte histograms and
+//! n-gram models of a few languages to tell text from noise.
+//!
+//! The models are trained on the small corpora in `corpora/`, a few
+//! KB each, so they know the common n-grams of each language well but
+//! have never seen most of the rare ones, which get the smoothing
+//! floor. Good enough to rank candidate plaintexts and tell the
+//! languages apart; for finer scoring train a model on a real corpus
+//! with the `train_model` binary and `Model::load` it.
+use crate::encoding::base64;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+pub mod entropy;
+pub mod model;
+pub mod triage;
+pub use entropy::{entropy, sliding_window_entropy};
+pub use model::Model;
+pub use triage::{triage, Guess, Triage};
+
 type Score = f32;
 
+/// The kinds of text we have corpora for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    Spanish,
+    /// JSON payloads, like API requests and log lines.
+    Json,
+    /// Base64 of English text.
+    Base64,
+}
+
+impl Language {
+    pub const ALL: [Language; 4] = [
+        Language::English,
+        Language::Spanish,
+        Language::Json,
+        Language::Base64,
+    ];
+
+    /// The model of the language, trained on first use.
+    pub fn model(self) -> &'static Model {
+        static MODELS: [OnceLock<Model>; 4] = [
+            OnceLock::new(),
+            OnceLock::new(),
+            OnceLock::new(),
+            OnceLock::new(),
+        ];
+        MODELS[self as usize].get_or_init(|| match self {
+            Language::English => Model::train(ENGLISH_CORPUS.as_bytes()),
+            Language::Spanish => Model::train(SPANISH_CORPUS.as_bytes()),
+            Language::Json => Model::train(JSON_CORPUS.as_bytes()),
+            Language::Base64 => Model::train(base64::encode(ENGLISH_CORPUS.as_bytes()).as_bytes()),
+        })
+    }
+}
+
+static ENGLISH_CORPUS: &str = include_str!("corpora/english.txt");
+static SPANISH_CORPUS: &str = include_str!("corpora/spanish.txt");
+static JSON_CORPUS: &str = include_str!("corpora/json.txt");
+
+/// The English model.
+pub fn english() -> &'static Model {
+    Language::English.model()
+}
+
+/// The language whose model scores the text best.
+pub fn detect(text: &[u8]) -> Language {
+    let score = |language: &Language| language.model().score(text);
+    *Language::ALL
+        .iter()
+        .max_by(|a, b| score(a).total_cmp(&score(b)))
+        .unwrap()
+}
+
 pub const LETTER_BY_FREQUENCY: [(char, u8); 26] = [
     ('e', 26),
     ('t', 25),
@@ -31,15 +103,52 @@ pub const LETTER_BY_FREQUENCY: [(char, u8); 26] = [
     ('z', 1),
 ];
 
-pub fn commonth_byte(ciphertext: &[u8]) -> Vec<(&u8, usize)> {
-    let mut counts = BTreeMap::new();
-    for byte in ciphertext.iter() {
-        *counts.entry(byte).or_insert(0) += 1;
+/// How many times every byte shows up.
+pub fn histogram(bytes: &[u8]) -> [usize; 256] {
+    let mut histogram = [0; 256];
+    for &b in bytes {
+        histogram[b as usize] += 1;
+    }
+    histogram
+}
+
+/// The bytes that show up, with their counts, the most common first.
+pub fn most_common(bytes: &[u8]) -> Vec<(u8, usize)> {
+    let mut counts: Vec<(u8, usize)> = histogram(bytes)
+        .iter()
+        .enumerate()
+        .filter(|&(_, &count)| count > 0)
+        .map(|(b, &count)| (b as u8, count))
+        .collect();
+    counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    counts
+}
+
+/// The index of coincidence: how likely two bytes picked at random
+/// are the same. About 0.066 for the letters of English, 1/26 for
+/// random letters and 1/256 for random bytes.
+pub fn index_of_coincidence(bytes: &[u8]) -> f64 {
+    if bytes.len() < 2 {
+        return 0.0;
     }
+    let pairs: usize = histogram(bytes)
+        .iter()
+        .map(|&count| count * count.saturating_sub(1))
+        .sum();
+    pairs as f64 / (bytes.len() * (bytes.len() - 1)) as f64
+}
 
-    let mut v = Vec::from_iter(counts.into_iter());
-    v.sort_by(|&(_, a), &(_, b)| b.cmp(&a));
-    v
+/// Same as `most_common`, ties going to the lowest byte,
+/// but borrowing the bytes from the ciphertext.
+pub fn commonth_byte(ciphertext: &[u8]) -> Vec<(&u8, usize)> {
+    let mut first = [0; 256];
+    for (i, &b) in ciphertext.iter().enumerate().rev() {
+        first[b as usize] = i;
+    }
+    most_common(ciphertext)
+        .into_iter()
+        .map(|(b, count)| (&ciphertext[first[b as usize]], count))
+        .collect()
 }
 
 pub fn triagrams_present(ascii_text: &str) -> Score {
@@ -117,4 +226,56 @@ mod tests {
         let score = analysis(txt);
         assert!(score < 0.50);
     }
+
+    #[test]
+    fn test_histogram() {
+        let histogram = histogram(b"hello");
+        assert_eq!(histogram[b'l' as usize], 2);
+        assert_eq!(histogram.iter().sum::<usize>(), 5);
+        assert_eq!(most_common(b"hello")[0], (b'l', 2));
+        assert_eq!(
+            commonth_byte(b"hello"),
+            vec![(&b'l', 2), (&b'e', 1), (&b'h', 1), (&b'o', 1)]
+        );
+    }
+
+    #[test]
+    fn test_index_of_coincidence() {
+        assert_eq!(index_of_coincidence(b"aaa"), 1.0);
+        assert_eq!(index_of_coincidence(b"abc"), 0.0);
+        let letters: Vec<u8> = ENGLISH_CORPUS
+            .bytes()
+            .filter(u8::is_ascii_alphabetic)
+            .map(|b| b.to_ascii_lowercase())
+            .collect();
+        let english = index_of_coincidence(&letters);
+        assert!(english > 0.06 && english < 0.07);
+    }
+
+    #[test]
+    fn test_detect() {
+        let detect = |text: &str| detect(text.as_bytes());
+        assert_eq!(detect("the cat sat on the mat"), Language::English);
+        assert_eq!(detect("el gato se sentó en la alfombra"), Language::Spanish);
+        assert_eq!(detect(r#"{"user": "bob", "id": 4}"#), Language::Json);
+        assert_eq!(detect("dGhlIGNhdCBzYXQgb24gdGhlIG1hdA=="), Language::Base64);
+    }
+
+    #[test]
+    fn test_single_byte_xor() {
+        let cipher_text = [
+            0x1b, 0x37, 0x37, 0x33, 0x31, 0x36, 0x3f, 0x78, 0x15, 0x1b, 0x7f, 0x2b, 0x78, 0x34,
+            0x31, 0x33, 0x3d, 0x78, 0x39, 0x78, 0x28, 0x37, 0x2d, 0x36, 0x3c, 0x78, 0x37, 0x3e,
+            0x78, 0x3a, 0x39, 0x3b, 0x37, 0x36,
+        ];
+        let decrypt = |key: u8| -> Vec<u8> { cipher_text.iter().map(|b| b ^ key).collect() };
+        let key = (0..=255)
+            .max_by(|&a, &b| {
+                english()
+                    .score(&decrypt(a))
+                    .total_cmp(&english().score(&decrypt(b)))
+            })
+            .unwrap();
+        assert_eq!(decrypt(key), b"Cooking MC's like a pound of bacon");
+    }
 }
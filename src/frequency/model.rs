@@ -0,0 +1,505 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+use std::{error, fmt, fs, io};
+
+/// The first bytes of a serialized model.
+const MAGIC: &[u8; 4] = b"NGRM";
+
+/// The version of the serialization format.
+const VERSION: u8 = 1;
+
+/// How to deal with n-grams which were never seen in the corpus,
+/// so a single odd byte doesn't make a text impossible.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Smoothing {
+    /// Count unseen n-grams as this fraction of an occurrence.
+    Floor(f64),
+    /// Additive (Laplace when 1) smoothing: add this to every count,
+    /// seen or not.
+    AddK(f64),
+}
+
+impl Default for Smoothing {
+    fn default() -> Self {
+        Smoothing::Floor(0.01)
+    }
+}
+
+/// Why a serialized model could not be loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelError {
+    /// It doesn't start with the magic bytes, so it's not a model.
+    BadMagic,
+    /// A model from a newer version of the format.
+    UnsupportedVersion(u8),
+    /// The smoothing is not one we know, or its parameter is negative.
+    InvalidSmoothing,
+    /// The data ended early, or has bytes after the model.
+    InvalidLength,
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModelError::BadMagic => write!(f, "not a model"),
+            ModelError::UnsupportedVersion(v) => write!(f, "unsupported model version {}", v),
+            ModelError::InvalidSmoothing => write!(f, "invalid smoothing"),
+            ModelError::InvalidLength => write!(f, "invalid model length"),
+        }
+    }
+}
+
+impl error::Error for ModelError {}
+
+/// A byte level language model: how often every byte, pair
+/// and triplet of bytes shows up in some corpus.
+/// Spaces, punctuation and case are just more bytes,
+/// unless the model folds case.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Model {
+    fold_case: bool,
+    smoothing: Smoothing,
+    unigrams: Vec<u64>,
+    bigrams: HashMap<[u8; 2], u64>,
+    trigrams: HashMap<[u8; 3], u64>,
+    // the total of unigrams, bigrams and trigrams
+    totals: [u64; 3],
+}
+
+impl Model {
+    /// An empty model, which knows nothing.
+    /// If `fold_case`, ASCII letters are lowercased
+    /// both when training and when scoring.
+    pub fn new(fold_case: bool) -> Self {
+        Model {
+            fold_case,
+            smoothing: Smoothing::default(),
+            unigrams: vec![0; 256],
+            bigrams: HashMap::new(),
+            trigrams: HashMap::new(),
+            totals: [0; 3],
+        }
+    }
+
+    /// Train a case sensitive model on a corpus.
+    pub fn train(corpus: &[u8]) -> Self {
+        let mut model = Model::new(false);
+        model.update(corpus);
+        model
+    }
+
+    /// Train a model on a corpus file.
+    pub fn from_corpus_file<P: AsRef<Path>>(path: P, fold_case: bool) -> io::Result<Self> {
+        let mut model = Model::new(fold_case);
+        model.update(&fs::read(path)?);
+        Ok(model)
+    }
+
+    /// The same model, smoothed some other way.
+    pub fn with_smoothing(mut self, smoothing: Smoothing) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    pub fn smoothing(&self) -> Smoothing {
+        self.smoothing
+    }
+
+    pub fn fold_case(&self) -> bool {
+        self.fold_case
+    }
+
+    /// Count the n-grams of some more text.
+    pub fn update(&mut self, corpus: &[u8]) {
+        let corpus = self.normalize(corpus);
+        for &b in corpus.iter() {
+            self.unigrams[b as usize] += 1;
+        }
+        for pair in corpus.windows(2) {
+            *self.bigrams.entry([pair[0], pair[1]]).or_insert(0) += 1;
+        }
+        for triplet in corpus.windows(3) {
+            *self
+                .trigrams
+                .entry([triplet[0], triplet[1], triplet[2]])
+                .or_insert(0) += 1;
+        }
+        self.totals[0] += corpus.len() as u64;
+        self.totals[1] += corpus.len().saturating_sub(1) as u64;
+        self.totals[2] += corpus.len().saturating_sub(2) as u64;
+    }
+
+    /// How many times the n-gram was seen, for n from 1 to 3.
+    pub fn count(&self, ngram: &[u8]) -> u64 {
+        match *ngram {
+            [a] => self.unigrams[a as usize],
+            [a, b] => *self.bigrams.get(&[a, b]).unwrap_or(&0),
+            [a, b, c] => *self.trigrams.get(&[a, b, c]).unwrap_or(&0),
+            _ => panic!("only n-grams of 1 to 3 bytes are modelled"),
+        }
+    }
+
+    /// The log10 of how likely a n-gram is, for n from 1 to 3.
+    pub fn log_prob(&self, ngram: &[u8]) -> f64 {
+        let count = self.count(ngram) as f64;
+        let total = self.totals[ngram.len() - 1] as f64;
+        let (count, total) = match self.smoothing {
+            Smoothing::Floor(floor) if count == 0.0 => (floor, total.max(1.0)),
+            Smoothing::Floor(_) => (count, total),
+            // every one of the 256^n possible n-grams gets k more
+            Smoothing::AddK(k) => (count + k, total + k * 256f64.powi(ngram.len() as i32)),
+        };
+        (count / total).log10()
+    }
+
+    /// The sum of the log10 probabilities of all the n-grams of the text.
+    pub fn log_likelihood(&self, text: &[u8], n: usize) -> f64 {
+        self.normalize(text)
+            .windows(n)
+            .map(|ngram| self.log_prob(ngram))
+            .sum()
+    }
+
+    /// How much the text looks like the corpus: the mean log10 probability
+    /// of its trigrams, or of its bigrams or bytes if it is too short.
+    /// The higher (the closer to zero), the better.
+    /// Scores only compare among texts of similar length.
+    pub fn score(&self, text: &[u8]) -> f64 {
+        if text.is_empty() {
+            return f64::NEG_INFINITY;
+        }
+        let n = text.len().min(3);
+        self.log_likelihood(text, n) / (text.len() - n + 1) as f64
+    }
+
+    /// Pearson's chi-squared statistic of the bytes of the text against
+    /// the ones expected from the model. The lower, the better.
+    pub fn chi_squared(&self, text: &[u8]) -> f64 {
+        let observed = self.histogram(text);
+        let len = text.len() as f64;
+        (0..256)
+            .map(|b| {
+                let expected = len * 10f64.powf(self.log_prob(&[b as u8]));
+                (observed[b] - expected).powi(2) / expected
+            })
+            .sum()
+    }
+
+    /// The cosine similarity between the byte frequencies of the text and
+    /// the model's, from 0 (nothing in common) to 1 (same proportions).
+    pub fn cosine_similarity(&self, text: &[u8]) -> f64 {
+        let observed = self.histogram(text);
+        let mut dot = 0.0;
+        let mut observed_norm = 0.0;
+        let mut expected_norm = 0.0;
+        for (&o, &e) in observed.iter().zip(self.unigrams.iter()) {
+            let e = e as f64;
+            dot += o * e;
+            observed_norm += o * o;
+            expected_norm += e * e;
+        }
+        if observed_norm == 0.0 || expected_norm == 0.0 {
+            return 0.0;
+        }
+        dot / (observed_norm.sqrt() * expected_norm.sqrt())
+    }
+
+    /// Serialize the model in a compact format: the counts as LEB128
+    /// varints and the n-grams seen, sorted, as deltas from the previous.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.push(self.fold_case as u8);
+        let (kind, parameter) = match self.smoothing {
+            Smoothing::Floor(floor) => (0, floor),
+            Smoothing::AddK(k) => (1, k),
+        };
+        bytes.push(kind);
+        bytes.extend_from_slice(&parameter.to_le_bytes());
+
+        for &count in &self.unigrams {
+            write_varint(&mut bytes, count);
+        }
+        let bigrams = self
+            .bigrams
+            .iter()
+            .map(|(&[a, b], &count)| (u32::from_be_bytes([0, 0, a, b]), count));
+        write_ngrams(&mut bytes, bigrams.collect());
+        let trigrams = self
+            .trigrams
+            .iter()
+            .map(|(&[a, b, c], &count)| (u32::from_be_bytes([0, a, b, c]), count));
+        write_ngrams(&mut bytes, trigrams.collect());
+        bytes
+    }
+
+    /// Load a model serialized with `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ModelError> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(ModelError::BadMagic);
+        }
+        let mut reader = Reader {
+            bytes: &bytes[MAGIC.len()..],
+        };
+        let version = reader.byte()?;
+        if version != VERSION {
+            return Err(ModelError::UnsupportedVersion(version));
+        }
+        let fold_case = reader.byte()? != 0;
+        let kind = reader.byte()?;
+        let mut parameter = [0; 8];
+        for p in parameter.iter_mut() {
+            *p = reader.byte()?;
+        }
+        let parameter = f64::from_le_bytes(parameter);
+        if parameter.is_nan() || parameter < 0.0 {
+            return Err(ModelError::InvalidSmoothing);
+        }
+        let smoothing = match kind {
+            0 => Smoothing::Floor(parameter),
+            1 => Smoothing::AddK(parameter),
+            _ => return Err(ModelError::InvalidSmoothing),
+        };
+
+        let mut model = Model::new(fold_case).with_smoothing(smoothing);
+        for count in model.unigrams.iter_mut() {
+            *count = reader.varint()?;
+        }
+        for (key, count) in reader.ngrams(0xFFFF)? {
+            let [_, _, a, b] = key.to_be_bytes();
+            model.bigrams.insert([a, b], count);
+        }
+        for (key, count) in reader.ngrams(0xFF_FFFF)? {
+            let [_, a, b, c] = key.to_be_bytes();
+            model.trigrams.insert([a, b, c], count);
+        }
+        if !reader.bytes.is_empty() {
+            return Err(ModelError::InvalidLength);
+        }
+        model.totals = [
+            checked_sum(model.unigrams.iter())?,
+            checked_sum(model.bigrams.values())?,
+            checked_sum(model.trigrams.values())?,
+        ];
+        Ok(model)
+    }
+
+    /// Write the serialized model to a file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    /// Read a serialized model from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Model::from_bytes(&fs::read(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn histogram(&self, text: &[u8]) -> [f64; 256] {
+        let mut histogram = [0.0; 256];
+        for &b in self.normalize(text).iter() {
+            histogram[b as usize] += 1.0;
+        }
+        histogram
+    }
+
+    fn normalize<'a>(&self, text: &'a [u8]) -> Cow<'a, [u8]> {
+        if self.fold_case {
+            Cow::Owned(text.to_ascii_lowercase())
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+// The number of n-grams, then for each, sorted, the delta
+// from the previous n-gram and its count.
+fn write_ngrams(bytes: &mut Vec<u8>, mut ngrams: Vec<(u32, u64)>) {
+    ngrams.sort_unstable();
+    write_varint(bytes, ngrams.len() as u64);
+    let mut previous = 0;
+    for (key, count) in ngrams {
+        write_varint(bytes, (key - previous) as u64);
+        write_varint(bytes, count);
+        previous = key;
+    }
+}
+
+/// The sum of counts read from a file, which may not fit.
+fn checked_sum<'a>(mut counts: impl Iterator<Item = &'a u64>) -> Result<u64, ModelError> {
+    counts
+        .try_fold(0u64, |total, &count| total.checked_add(count))
+        .ok_or(ModelError::InvalidLength)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, ModelError> {
+        let (&first, rest) = self.bytes.split_first().ok_or(ModelError::InvalidLength)?;
+        self.bytes = rest;
+        Ok(first)
+    }
+
+    fn varint(&mut self) -> Result<u64, ModelError> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        // longer than any u64
+        Err(ModelError::InvalidLength)
+    }
+
+    /// The n-grams, as keys no bigger than `max_key`.
+    fn ngrams(&mut self, max_key: u64) -> Result<Vec<(u32, u64)>, ModelError> {
+        let len = self.varint()?;
+        // don't trust the length to allocate, each n-gram takes two bytes at least
+        let mut ngrams = Vec::with_capacity((len as usize).min(self.bytes.len() / 2));
+        let mut key: u64 = 0;
+        for _ in 0..len {
+            key = key
+                .checked_add(self.varint()?)
+                .ok_or(ModelError::InvalidLength)?;
+            if key > max_key {
+                return Err(ModelError::InvalidLength);
+            }
+            ngrams.push((key as u32, self.varint()?));
+        }
+        Ok(ngrams)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts() {
+        let model = Model::train(b"abab");
+        assert_eq!(model.count(b"a"), 2);
+        assert_eq!(model.count(b"ab"), 2);
+        assert_eq!(model.count(b"ba"), 1);
+        assert_eq!(model.count(b"bab"), 1);
+        assert_eq!(model.count(b"c"), 0);
+        assert_eq!(model.log_prob(b"a"), 0.5f64.log10());
+    }
+
+    #[test]
+    fn test_fold_case() {
+        let mut model = Model::new(true);
+        model.update(b"Hello HELLO");
+        assert_eq!(model.count(b"h"), 2);
+        assert_eq!(model.count(b"H"), 0);
+        assert_eq!(model.score(b"HELLO"), model.score(b"hello"));
+    }
+
+    #[test]
+    fn test_metrics() {
+        let model = Model::train(b"the quick brown fox jumps over the lazy dog");
+        let text = b"the lazy fox";
+        let noise = b"\x8f\x01\xfe\x99zq\x02\x7f\x80\xaa\x11\x13";
+        assert!(model.score(text) > model.score(noise));
+        assert!(model.chi_squared(text) < model.chi_squared(noise));
+        assert!(model.cosine_similarity(text) > model.cosine_similarity(noise));
+        assert_eq!(model.score(b""), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_smoothing() {
+        let model = Model::train(b"aab");
+        assert_eq!(model.log_prob(b"c"), (0.01f64 / 3.0).log10());
+        let model = model.with_smoothing(Smoothing::AddK(1.0));
+        assert_eq!(model.log_prob(b"a"), (3.0f64 / 259.0).log10());
+        assert_eq!(model.log_prob(b"c"), (1.0f64 / 259.0).log10());
+        assert_eq!(model.log_prob(b"ab"), (2.0f64 / 65538.0).log10());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let mut model = Model::new(true).with_smoothing(Smoothing::AddK(0.5));
+        model.update("Hello, World! ¡Hola, mundo!".repeat(100).as_bytes());
+        let bytes = model.to_bytes();
+        assert_eq!(Model::from_bytes(&bytes), Ok(model));
+
+        assert_eq!(Model::from_bytes(b"JSON"), Err(ModelError::BadMagic));
+        assert_eq!(
+            Model::from_bytes(b"NGRM\x02"),
+            Err(ModelError::UnsupportedVersion(2))
+        );
+        assert_eq!(
+            Model::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ModelError::InvalidLength)
+        );
+        let mut invalid = bytes.clone();
+        invalid[6] = 7;
+        assert_eq!(
+            Model::from_bytes(&invalid),
+            Err(ModelError::InvalidSmoothing)
+        );
+    }
+
+    #[test]
+    fn test_serialization_overflowing_totals() {
+        let mut invalid = Model::new(false).to_bytes();
+        // the unigram counts start after the header, one byte each when 0
+        let unigrams = invalid.len() - 2 - 256;
+        let mut max = Vec::new();
+        write_varint(&mut max, u64::MAX);
+        invalid[unigrams + 1] = 1;
+        invalid.splice(unigrams..unigrams + 1, max);
+        assert_eq!(Model::from_bytes(&invalid), Err(ModelError::InvalidLength));
+    }
+
+    #[test]
+    fn test_serialization_key_too_big() {
+        let empty = Model::new(false).to_bytes();
+        let header = &empty[..empty.len() - 2];
+        // a bigram 0x010203, with a byte too many
+        let mut invalid = header.to_vec();
+        invalid.push(1);
+        write_varint(&mut invalid, 0x01_0203);
+        invalid.extend_from_slice(&[1, 0]);
+        assert_eq!(Model::from_bytes(&invalid), Err(ModelError::InvalidLength));
+        // the same key is fine for a trigram
+        let mut valid = header.to_vec();
+        valid.extend_from_slice(&[0, 1]);
+        write_varint(&mut valid, 0x01_0203);
+        valid.push(1);
+        let model = Model::from_bytes(&valid).unwrap();
+        assert_eq!(model.count(&[1, 2, 3]), 1);
+    }
+
+    #[test]
+    fn test_serialization_overflowing_delta() {
+        // an empty model ends with the number of bigrams and of trigrams
+        let empty = Model::new(false).to_bytes();
+        let mut invalid = empty[..empty.len() - 2].to_vec();
+        // two bigrams, the second a delta of u64::MAX from the first
+        invalid.extend_from_slice(&[2, 1, 1]);
+        write_varint(&mut invalid, u64::MAX);
+        invalid.extend_from_slice(&[1, 0]);
+        assert_eq!(Model::from_bytes(&invalid), Err(ModelError::InvalidLength));
+    }
+
+    #[test]
+    fn test_serialization_is_compact() {
+        let model = Model::train(include_bytes!("corpora/english.txt"));
+        // the corpus is less than 6KB
+        assert!(model.to_bytes().len() < 12_000);
+    }
+}
@@ -0,0 +1,313 @@
+use super::entropy::entropy;
+use super::{detect, index_of_coincidence, Language};
+use crate::bytes;
+use crate::encoding::{base64, hex};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Longest repeating XOR key looked for.
+const MAX_KEY_LENGTH: usize = 40;
+
+/// Magic numbers of common compressed formats.
+const COMPRESSED_MAGIC: [&[u8]; 6] = [
+    &[0x1f, 0x8b],                         // gzip
+    &[0x28, 0xb5, 0x2f, 0xfd],             // zstd
+    &[0xfd, b'7', b'z', b'X', b'Z', 0x00], // xz
+    b"BZh",                                // bzip2
+    b"PK\x03\x04",                         // zip
+    b"7z\xbc\xaf\x27\x1c",                 // 7z
+];
+
+/// What a blob could be.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Guess {
+    /// Printable text, in the language whose model likes it best.
+    Text(Language),
+    Hex,
+    Base64,
+    /// Starts with the magic number of a compressed format.
+    Compressed,
+    /// Maximum entropy: encrypted, random, or both.
+    Random,
+    /// The same 16 byte blocks show up more than once, as with ECB.
+    RepeatedBlocks {
+        block_size: usize,
+        repeated: usize,
+    },
+    /// Text XORed with a repeating key of this length.
+    RepeatingKeyXor {
+        key_length: usize,
+    },
+}
+
+impl fmt::Display for Guess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Guess::Text(language) => write!(f, "{:?} text", language),
+            Guess::Hex => write!(f, "hex encoded"),
+            Guess::Base64 => write!(f, "base64 encoded"),
+            Guess::Compressed => write!(f, "compressed"),
+            Guess::Random => write!(f, "random or encrypted"),
+            Guess::RepeatedBlocks {
+                block_size,
+                repeated,
+            } => write!(
+                f,
+                "ECB with {} repeated blocks of {} bytes",
+                repeated, block_size
+            ),
+            Guess::RepeatingKeyXor { key_length } => {
+                write!(f, "repeating-key XOR, likely key length {}", key_length)
+            }
+        }
+    }
+}
+
+/// What we could tell about a blob, before attacking it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triage {
+    pub len: usize,
+    pub entropy: f64,
+    pub index_of_coincidence: f64,
+    /// Most likely first, empty if we have no idea.
+    pub guesses: Vec<Guess>,
+    /// For encoded blobs, the triage of what was encoded.
+    pub decoded: Option<Box<Triage>>,
+}
+
+impl fmt::Display for Triage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "length: {} bytes", self.len)?;
+        writeln!(f, "entropy: {:.3} bits per byte", self.entropy)?;
+        writeln!(f, "index of coincidence: {:.4}", self.index_of_coincidence)?;
+        if self.guesses.is_empty() {
+            writeln!(f, "guess: unknown")?;
+        }
+        for guess in &self.guesses {
+            writeln!(f, "guess: {}", guess)?;
+        }
+        if let Some(decoded) = &self.decoded {
+            writeln!(f, "decoded:")?;
+            for line in decoded.to_string().lines() {
+                writeln!(f, "  {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Guess what a blob is.
+pub fn triage(data: &[u8]) -> Triage {
+    let entropy = entropy(data);
+    let mut guesses = Vec::new();
+    let mut decoded = None;
+
+    // words separated by spaces may well decode as base64 once the
+    // spaces are gone, but they are much more likely to be text
+    let text = is_text(data);
+    let encoded = if text && has_spaces(data) {
+        None
+    } else {
+        decode(data)
+    };
+
+    if let Some((guess, bytes)) = encoded {
+        guesses.push(guess);
+        decoded = Some(Box::new(triage(&bytes)));
+    } else if text {
+        guesses.push(Guess::Text(detect(data)));
+    } else if !data.is_empty() {
+        if COMPRESSED_MAGIC.iter().any(|magic| data.starts_with(magic)) {
+            guesses.push(Guess::Compressed);
+        }
+        if let Some(guess) = repeated_blocks(data) {
+            guesses.push(guess);
+        }
+        // the most entropy there can be in so few bytes
+        let max_entropy = (data.len() as f64).log2().min(8.0);
+        if entropy > 0.9 * max_entropy {
+            if !guesses.contains(&Guess::Compressed) {
+                guesses.push(Guess::Random);
+            }
+        } else if let Some(key_length) = xor_key_length(data) {
+            guesses.push(Guess::RepeatingKeyXor { key_length });
+        }
+    }
+
+    Triage {
+        len: data.len(),
+        entropy,
+        index_of_coincidence: index_of_coincidence(data),
+        guesses,
+        decoded,
+    }
+}
+
+/// Printable, and mostly letters and spaces. Text XORed with
+/// letters is printable too, but mostly punctuation.
+fn is_text(data: &[u8]) -> bool {
+    let words = data
+        .iter()
+        .filter(|&&b| b.is_ascii_alphabetic() || b == b' ')
+        .count();
+    !data.is_empty()
+        && bytes::printable_ratio(data) > 0.95
+        && words as f64 / data.len() as f64 > 0.6
+}
+
+/// Spaces between the first and last printable characters. Encodings
+/// are wrapped with newlines, if at all, but sentences have spaces.
+fn has_spaces(data: &[u8]) -> bool {
+    let start = data.iter().position(|b| !b.is_ascii_whitespace());
+    let end = data.iter().rposition(|b| !b.is_ascii_whitespace());
+    match (start, end) {
+        (Some(start), Some(end)) => data[start..end].contains(&b' '),
+        _ => false,
+    }
+}
+
+/// Decode hex or base64, ignoring whitespace.
+fn decode(data: &[u8]) -> Option<(Guess, Vec<u8>)> {
+    let compact: String = String::from_utf8_lossy(data).split_whitespace().collect();
+    if compact.len() < 4 {
+        return None;
+    }
+    if let Ok(bytes) = hex::from_string(&compact) {
+        return Some((Guess::Hex, bytes));
+    }
+    if let Ok(bytes) = base64::decode(&compact) {
+        return Some((Guess::Base64, bytes));
+    }
+    None
+}
+
+/// Repeated aligned blocks of 16 bytes, the AES block size, if any.
+fn repeated_blocks(data: &[u8]) -> Option<Guess> {
+    let block_size = 16;
+    let mut counts = HashMap::new();
+    for block in bytes::blocks(data, block_size) {
+        *counts.entry(block).or_insert(0) += 1;
+    }
+    let repeated: usize = counts.values().filter(|&&c| c > 1).sum();
+    if repeated == 0 {
+        return None;
+    }
+    Some(Guess::RepeatedBlocks {
+        block_size,
+        repeated,
+    })
+}
+
+/// With the right key length every column of the data is single byte
+/// XORed text, which keeps the index of coincidence of text.
+/// Multiples of the key length are as good, so take the shortest
+/// one close enough to the best.
+fn xor_key_length(data: &[u8]) -> Option<usize> {
+    let iocs: Vec<(usize, f64)> = (1..=MAX_KEY_LENGTH.min(data.len() / 4))
+        .map(|key_length| {
+            let columns = bytes::transpose(data, key_length);
+            let ioc = columns
+                .iter()
+                .map(|column| index_of_coincidence(column))
+                .sum::<f64>()
+                / columns.len() as f64;
+            (key_length, ioc)
+        })
+        .collect();
+    let best = iocs.iter().map(|&(_, ioc)| ioc).fold(0.0, f64::max);
+    // text has an index of coincidence of 0.06 or more
+    if best < 0.05 {
+        return None;
+    }
+    iocs.iter()
+        .find(|&&(_, ioc)| ioc > 0.9 * best)
+        .map(|&(key_length, _)| key_length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+    use crate::symm::{self, padding::Padding};
+
+    #[test]
+    fn test_text() {
+        let report = triage(super::super::ENGLISH_CORPUS.as_bytes());
+        assert_eq!(report.guesses, vec![Guess::Text(Language::English)]);
+        assert!(report.entropy > 4.0 && report.entropy < 5.0);
+    }
+
+    #[test]
+    fn test_sentences_are_not_base64() {
+        for sentence in ["attack at dawn", "meet me at noon"].iter() {
+            let report = triage(sentence.as_bytes());
+            assert_eq!(report.guesses.len(), 1, "{}", sentence);
+            assert!(
+                matches!(report.guesses[0], Guess::Text(_)),
+                "{}: {:?}",
+                sentence,
+                report.guesses
+            );
+            assert!(report.decoded.is_none());
+        }
+        // wrapped base64 is still base64
+        let wrapped = base64::encode(&super::super::ENGLISH_CORPUS.as_bytes()[..200]);
+        let wrapped: Vec<u8> = wrapped
+            .as_bytes()
+            .chunks(76)
+            .collect::<Vec<_>>()
+            .join(&b'\n');
+        assert_eq!(triage(&wrapped).guesses, vec![Guess::Base64]);
+    }
+
+    #[test]
+    fn test_encoded() {
+        let text = b"It is a truth universally acknowledged";
+        let report = triage(hex::to_string(text).as_bytes());
+        assert_eq!(report.guesses, vec![Guess::Hex]);
+        let decoded = report.decoded.unwrap();
+        assert_eq!(decoded.guesses, vec![Guess::Text(Language::English)]);
+
+        let report = triage(base64::encode(&[0xff; 32]).as_bytes());
+        assert_eq!(report.guesses, vec![Guess::Base64]);
+    }
+
+    #[test]
+    fn test_random() {
+        let data = random::with_rng(1, || random::get_random(4096));
+        let report = triage(&data);
+        assert_eq!(report.guesses, vec![Guess::Random]);
+        assert!(report.entropy > 7.9);
+
+        let mut gzip = vec![0x1f, 0x8b, 0x08, 0x00];
+        gzip.extend(data);
+        assert_eq!(triage(&gzip).guesses, vec![Guess::Compressed]);
+    }
+
+    #[test]
+    fn test_repeating_key_xor() {
+        let text = super::super::ENGLISH_CORPUS.as_bytes();
+        let report = triage(&bytes::repeating_xor(text, b"ICEBERG"));
+        assert_eq!(
+            report.guesses,
+            vec![Guess::RepeatingKeyXor { key_length: 7 }]
+        );
+    }
+
+    #[test]
+    fn test_ecb() {
+        let data = random::with_rng(2, || {
+            let key = random::get_random(16);
+            symm::aes_encrypt(&key, &[b'A'; 64], symm::Mode::ECB, Padding::PKCS7)
+        });
+        let report = triage(&data);
+        assert_eq!(
+            report.guesses[0],
+            Guess::RepeatedBlocks {
+                block_size: 16,
+                repeated: 4
+            }
+        );
+        assert!(report.to_string().contains("ECB with 4 repeated blocks"));
+    }
+}
@@ -1,27 +1,25 @@
 pub mod bytes;
+pub mod classical;
 pub mod encoding;
 pub mod frequency;
+pub mod hash;
+pub mod math;
 pub mod random;
+pub mod sharing;
+pub mod sidechannel;
 pub mod symm;
 
-pub fn position_of_block_in(ciphertext: &[u8], block: &[u8]) -> usize {
-    let mut position = 0;
-    for cipherblock in ciphertext.chunks(16) {
-        if cipherblock != block {
-            position += 1;
-        } else {
-            break;
-        }
-    }
-    position
+/// The index of the first `block_size` block of the ciphertext
+/// equal to `block`, or the number of blocks if there is none.
+pub fn position_of_block_in(ciphertext: &[u8], block: &[u8], block_size: usize) -> usize {
+    bytes::blocks(ciphertext, block_size)
+        .position(|cipherblock| cipherblock == block)
+        .unwrap_or_else(|| bytes::blocks(ciphertext, block_size).len())
 }
 
-pub fn count_block_in_ciphertext(ciphertext: &[u8], block: &[u8]) -> usize {
-    let mut repetitions = 0;
-    for cipherblock in ciphertext.chunks(16) {
-        if cipherblock == block {
-            repetitions += 1;
-        }
-    }
-    repetitions
+/// How many `block_size` blocks of the ciphertext are equal to `block`.
+pub fn count_block_in_ciphertext(ciphertext: &[u8], block: &[u8], block_size: usize) -> usize {
+    bytes::blocks(ciphertext, block_size)
+        .filter(|&cipherblock| cipherblock == block)
+        .count()
 }
@@ -2,7 +2,19 @@ mod first_set;
 mod second_set;
 mod third_set;
 
+use crypto::random;
+use std::env;
+
 fn main() {
+    // SEED=<u32> makes every key, IV and prefix the challenges
+    // generate reproducible, handy when something breaks.
+    match env::var("SEED").ok().and_then(|seed| seed.parse().ok()) {
+        Some(seed) => random::with_rng(seed, run),
+        None => run(),
+    }
+}
+
+fn run() {
     // first_set::xor_cypher();
     // first_set::xor_file();
     // first_set::xor_encrypt();
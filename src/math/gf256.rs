@@ -0,0 +1,399 @@
+//! The Rijndael finite field GF(2^8): bytes as polynomials with coefficients
+//! in GF(2), modulo x^8 + x^4 + x^3 + x + 1. Adding is XOR, and multiplying
+//! goes through log and antilog tables, as every non-zero element is a
+//! power of the generator.
+//! See section 4 of FIPS-197.
+
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Sub};
+
+/// The irreducible polynomial the field is modulo of.
+pub const MODULUS: u16 = 0x11B;
+
+/// x + 1 generates the multiplicative group; x, which is 2, does not.
+pub const GENERATOR: u8 = 0x03;
+
+struct Tables {
+    // the powers of the generator, twice, so log a + log b needs no modulo
+    exp: [u8; 510],
+    log: [u8; 256],
+}
+
+impl Tables {
+    const fn new() -> Self {
+        let mut exp = [0; 510];
+        let mut log = [0; 256];
+        let mut x: u16 = 1;
+        let mut i = 0;
+        while i < 255 {
+            exp[i] = x as u8;
+            exp[i + 255] = x as u8;
+            log[x as usize] = i as u8;
+            // x * 3 = x * 2 + x
+            x ^= x << 1;
+            if x & 0x100 != 0 {
+                x ^= MODULUS;
+            }
+            i += 1;
+        }
+        Tables { exp, log }
+    }
+}
+
+static TABLES: Tables = Tables::new();
+
+/// Multiply two bytes in the field.
+pub fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    TABLES.exp[TABLES.log[a as usize] as usize + TABLES.log[b as usize] as usize]
+}
+
+/// Divide two bytes in the field. Panics if `b` is zero.
+pub fn div(a: u8, b: u8) -> u8 {
+    assert!(b != 0, "division by zero in GF(2^8)");
+    if a == 0 {
+        return 0;
+    }
+    TABLES.exp[TABLES.log[a as usize] as usize + 255 - TABLES.log[b as usize] as usize]
+}
+
+/// The multiplicative inverse, None for zero.
+pub fn inverse(a: u8) -> Option<u8> {
+    if a == 0 {
+        return None;
+    }
+    Some(TABLES.exp[255 - TABLES.log[a as usize] as usize])
+}
+
+/// `a` to the `n`th power. Zero to the zero is one.
+pub fn pow(a: u8, n: u32) -> u8 {
+    if n == 0 {
+        return 1;
+    }
+    if a == 0 {
+        return 0;
+    }
+    let log = TABLES.log[a as usize] as u64 * n as u64 % 255;
+    TABLES.exp[log as usize]
+}
+
+/// The generator to the `n`th power.
+pub fn exp(n: u8) -> u8 {
+    TABLES.exp[n as usize]
+}
+
+/// The power the generator must be raised to to get `a`, None for zero.
+pub fn log(a: u8) -> Option<u8> {
+    if a == 0 {
+        return None;
+    }
+    Some(TABLES.log[a as usize])
+}
+
+/// An element of the field, with the operators of the field.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Gf256(pub u8);
+
+impl Gf256 {
+    pub const ZERO: Gf256 = Gf256(0);
+    pub const ONE: Gf256 = Gf256(1);
+
+    pub fn inverse(self) -> Option<Self> {
+        inverse(self.0).map(Gf256)
+    }
+
+    pub fn pow(self, n: u32) -> Self {
+        Gf256(pow(self.0, n))
+    }
+}
+
+impl From<u8> for Gf256 {
+    fn from(n: u8) -> Self {
+        Gf256(n)
+    }
+}
+
+impl Add for Gf256 {
+    type Output = Self;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn add(self, rhs: Self) -> Self {
+        Gf256(self.0 ^ rhs.0)
+    }
+}
+
+impl AddAssign for Gf256 {
+    #[allow(clippy::suspicious_op_assign_impl)]
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 ^= rhs.0;
+    }
+}
+
+// In characteristic two, subtracting is adding.
+impl Sub for Gf256 {
+    type Output = Self;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, rhs: Self) -> Self {
+        self + rhs
+    }
+}
+
+impl Mul for Gf256 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Gf256(mul(self.0, rhs.0))
+    }
+}
+
+impl MulAssign for Gf256 {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl Div for Gf256 {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        Gf256(div(self.0, rhs.0))
+    }
+}
+
+/// A polynomial with coefficients in the field,
+/// the constant term first. Never has leading zeroes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Poly {
+    coefficients: Vec<Gf256>,
+}
+
+impl Poly {
+    pub fn new(coefficients: Vec<Gf256>) -> Self {
+        let mut poly = Poly { coefficients };
+        poly.trim();
+        poly
+    }
+
+    pub fn zero() -> Self {
+        Poly {
+            coefficients: Vec::new(),
+        }
+    }
+
+    /// The coefficients, the constant term first.
+    pub fn coefficients(&self) -> &[Gf256] {
+        &self.coefficients
+    }
+
+    /// None for the zero polynomial.
+    pub fn degree(&self) -> Option<usize> {
+        self.coefficients.len().checked_sub(1)
+    }
+
+    /// The value of the polynomial at `x`, by Horner's method.
+    pub fn eval(&self, x: Gf256) -> Gf256 {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(Gf256::ZERO, |acc, &c| acc * x + c)
+    }
+
+    /// Every coefficient multiplied by `c`.
+    pub fn scale(&self, c: Gf256) -> Self {
+        Poly::new(self.coefficients.iter().map(|&a| a * c).collect())
+    }
+
+    /// The quotient and remainder of dividing by `divisor`.
+    /// Panics if it is zero.
+    pub fn div_rem(&self, divisor: &Poly) -> (Poly, Poly) {
+        let divisor_degree = divisor.degree().expect("division by the zero polynomial");
+        let lead_inverse = divisor.coefficients[divisor_degree].inverse().unwrap();
+        let mut remainder = self.coefficients.clone();
+        let quotient_len = (remainder.len() + 1).saturating_sub(divisor.coefficients.len());
+        let mut quotient = vec![Gf256::ZERO; quotient_len];
+        for i in (0..quotient_len).rev() {
+            let factor = remainder[i + divisor_degree] * lead_inverse;
+            quotient[i] = factor;
+            for (j, &d) in divisor.coefficients.iter().enumerate() {
+                remainder[i + j] += factor * d;
+            }
+        }
+        (Poly::new(quotient), Poly::new(remainder))
+    }
+
+    /// The polynomial of least degree going through all the points,
+    /// by Lagrange interpolation. None if two points share an x.
+    pub fn interpolate(points: &[(Gf256, Gf256)]) -> Option<Self> {
+        let mut result = Poly::zero();
+        for (i, &(xi, yi)) in points.iter().enumerate() {
+            // the basis polynomial, one at xi and zero at every other x
+            let mut basis = Poly::new(vec![Gf256::ONE]);
+            let mut denominator = Gf256::ONE;
+            for (j, &(xj, _)) in points.iter().enumerate() {
+                if i != j {
+                    basis = &basis * &Poly::new(vec![xj, Gf256::ONE]);
+                    denominator *= xi - xj;
+                }
+            }
+            result = &result + &basis.scale(yi * denominator.inverse()?);
+        }
+        Some(result)
+    }
+
+    /// The value at `x` of the polynomial going through all the points,
+    /// without building it. None if two points share an x.
+    pub fn interpolate_at(points: &[(Gf256, Gf256)], x: Gf256) -> Option<Gf256> {
+        let mut value = Gf256::ZERO;
+        for (i, &(xi, yi)) in points.iter().enumerate() {
+            let mut numerator = Gf256::ONE;
+            let mut denominator = Gf256::ONE;
+            for (j, &(xj, _)) in points.iter().enumerate() {
+                if i != j {
+                    numerator *= x - xj;
+                    denominator *= xi - xj;
+                }
+            }
+            value += yi * numerator * denominator.inverse()?;
+        }
+        Some(value)
+    }
+
+    fn trim(&mut self) {
+        while self.coefficients.last() == Some(&Gf256::ZERO) {
+            self.coefficients.pop();
+        }
+    }
+}
+
+impl Add for &Poly {
+    type Output = Poly;
+
+    fn add(self, rhs: &Poly) -> Poly {
+        let len = self.coefficients.len().max(rhs.coefficients.len());
+        let coefficient = |p: &Poly, i| *p.coefficients.get(i).unwrap_or(&Gf256::ZERO);
+        Poly::new(
+            (0..len)
+                .map(|i| coefficient(self, i) + coefficient(rhs, i))
+                .collect(),
+        )
+    }
+}
+
+impl Mul for &Poly {
+    type Output = Poly;
+
+    fn mul(self, rhs: &Poly) -> Poly {
+        if self.coefficients.is_empty() || rhs.coefficients.is_empty() {
+            return Poly::zero();
+        }
+        let mut product = vec![Gf256::ZERO; self.coefficients.len() + rhs.coefficients.len() - 1];
+        for (i, &a) in self.coefficients.iter().enumerate() {
+            for (j, &b) in rhs.coefficients.iter().enumerate() {
+                product[i + j] += a * b;
+            }
+        }
+        Poly::new(product)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poly(coefficients: &[u8]) -> Poly {
+        Poly::new(coefficients.iter().map(|&c| Gf256(c)).collect())
+    }
+
+    #[test]
+    fn test_mul() {
+        // FIPS-197 section 4.2
+        assert_eq!(mul(0x57, 0x83), 0xC1);
+        assert_eq!(mul(0x57, 0x13), 0xFE);
+        assert_eq!(mul(0x57, 0x02), 0xAE);
+        assert_eq!(mul(0, 0x13), 0);
+    }
+
+    #[test]
+    fn test_tables_match_slow_multiplication() {
+        let slow = |mut a: u8, mut b: u8| {
+            let mut product = 0;
+            while b != 0 {
+                if b & 1 == 1 {
+                    product ^= a;
+                }
+                // xtime, section 4.2.1
+                a = if a & 0x80 != 0 {
+                    (a << 1) ^ 0x1B
+                } else {
+                    a << 1
+                };
+                b >>= 1;
+            }
+            product
+        };
+        for a in 0..=255 {
+            for b in 0..=255 {
+                assert_eq!(mul(a, b), slow(a, b));
+            }
+        }
+    }
+
+    #[test]
+    fn test_inverse_and_div() {
+        // FIPS-197 section 4.4
+        assert_eq!(inverse(0x53), Some(0xCA));
+        assert_eq!(inverse(0), None);
+        for a in 1..=255 {
+            assert_eq!(mul(a, inverse(a).unwrap()), 1);
+            assert_eq!(div(mul(a, 0x42), a), 0x42);
+        }
+    }
+
+    #[test]
+    fn test_pow_exp_log() {
+        assert_eq!(pow(0x02, 8), 0x1B);
+        assert_eq!(pow(0x57, 0), 1);
+        assert_eq!(pow(0, 3), 0);
+        assert_eq!(pow(0x57, 254), inverse(0x57).unwrap());
+        assert_eq!(exp(log(0x57).unwrap()), 0x57);
+        assert_eq!(Gf256(2).pow(4) * Gf256(2).pow(4), Gf256(0x1B));
+    }
+
+    #[test]
+    fn test_poly() {
+        let p = poly(&[1, 2, 3, 0, 0]);
+        assert_eq!(p.degree(), Some(2));
+        assert_eq!(Poly::zero().degree(), None);
+        // 1 + 2 * 2 + 3 * 4
+        assert_eq!(p.eval(Gf256(2)), Gf256(1 ^ 4 ^ mul(3, 4)));
+        assert_eq!(&p + &p, Poly::zero());
+        // (x + 1)(x + 1) = x^2 + 1
+        assert_eq!(&poly(&[1, 1]) * &poly(&[1, 1]), poly(&[1, 0, 1]));
+    }
+
+    #[test]
+    fn test_div_rem() {
+        let a = poly(&[7, 0x57, 3, 0x83, 9]);
+        let b = poly(&[2, 1, 0x13]);
+        let (q, r) = a.div_rem(&b);
+        assert!(r.degree() < b.degree());
+        assert_eq!(&(&q * &b) + &r, a);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let p = poly(&[0x42, 0x13, 0x99]);
+        let points: Vec<(Gf256, Gf256)> = [1, 2, 3]
+            .iter()
+            .map(|&x| (Gf256(x), p.eval(Gf256(x))))
+            .collect();
+        assert_eq!(Poly::interpolate(&points), Some(p));
+        assert_eq!(Poly::interpolate_at(&points, Gf256(0)), Some(Gf256(0x42)));
+        assert_eq!(
+            Poly::interpolate_at(&[points[0], points[0]], Gf256(0)),
+            None
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use super::{bounded, Random, Result};
+
+/// Size of the additive feedback table used by glibc's TYPE_3 generator.
+const DEGREE: usize = 34;
+
+/// glibc's default `random()`/`rand()`, the TYPE_3 additive feedback
+/// generator: r[i] = r[i - 31] + r[i - 3], outputting r[i] >> 1.
+#[derive(Clone)]
+pub struct GlibcRandom {
+    table: [u32; DEGREE],
+    index: usize,
+}
+
+impl GlibcRandom {
+    /// Same as calling `srand(seed)`.
+    pub fn new(seed: u32) -> Self {
+        let mut r = [0u32; DEGREE];
+        r[0] = if seed == 0 { 1 } else { seed };
+        for i in 1..31 {
+            // glibc does this with signed 32 bit arithmetic
+            let product = (16807 * r[i - 1] as i32 as i64) % 2147483647;
+            r[i] = if product < 0 {
+                (product + 2147483647) as u32
+            } else {
+                product as u32
+            };
+        }
+        for i in 31..DEGREE {
+            r[i] = r[i - 31];
+        }
+
+        let mut glibc = Self { table: r, index: 0 };
+        // srand() throws away the first 310 values
+        for _ in DEGREE..344 {
+            glibc.feedback();
+        }
+        glibc
+    }
+
+    fn feedback(&mut self) -> u32 {
+        let value = self.table[(self.index + DEGREE - 31) % DEGREE]
+            .wrapping_add(self.table[(self.index + DEGREE - 3) % DEGREE]);
+        self.table[self.index] = value;
+        self.index = (self.index + 1) % DEGREE;
+        value
+    }
+}
+
+/// Predicts the next output of glibc's `rand()` from, at least,
+/// the last 31 outputs.
+/// Outputs drop the lowest bit of the table, so the sum may be off
+/// by one: both candidates are returned, the likeliest first.
+pub fn predict(outputs: &[usize]) -> Option<[usize; 2]> {
+    if outputs.len() < 31 {
+        return None;
+    }
+    let n = outputs.len();
+    let next = (outputs[n - 31] + outputs[n - 3]) & 0x7FFFFFFF;
+    Some([next, (next + 1) & 0x7FFFFFFF])
+}
+
+impl Random for GlibcRandom {
+    fn in_range(&mut self, floor: usize, ceiling: usize) -> Result<usize> {
+        bounded(floor, ceiling, 31, || Ok(self.next().unwrap() as u64))
+    }
+
+    fn flip_coin(&mut self) -> Result<bool> {
+        Ok(self.in_range(0, 2)? == 0)
+    }
+
+    fn get(&mut self, bytes: usize) -> Result<Vec<usize>> {
+        Ok(self.take(bytes).collect())
+    }
+}
+
+impl Iterator for GlibcRandom {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        Some((self.feedback() >> 1) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glibc_rand() {
+        // srand(1); rand(); rand(); rand();
+        let mut glibc = GlibcRandom::new(1);
+        assert_eq!(glibc.next(), Some(1804289383));
+        assert_eq!(glibc.next(), Some(846930886));
+        assert_eq!(glibc.next(), Some(1681692777));
+    }
+
+    #[test]
+    fn test_predict() {
+        let mut glibc = GlibcRandom::new(1337);
+        let mut outputs: Vec<usize> = glibc.clone().take(31).collect();
+        for _ in 0..31 {
+            glibc.next();
+        }
+        for _ in 0..1000 {
+            let candidates = predict(&outputs).unwrap();
+            let next = glibc.next().unwrap();
+            assert!(candidates.contains(&next));
+            outputs.push(next);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use super::{bounded, Random, Result};
+
+const MULTIPLIER: u64 = 0x5DEECE66D;
+const ADDEND: u64 = 0xB;
+const MASK: u64 = (1 << 48) - 1;
+
+/// The linear congruential generator behind `java.util.Random`.
+/// The state is 48 bits long and every `next()` gives the
+/// top 32 bits of it, same as Java's `nextInt()`.
+#[derive(Clone)]
+pub struct JavaRandom {
+    seed: u64,
+}
+
+impl JavaRandom {
+    /// Same as `new Random(seed)` in Java.
+    pub fn new(seed: u64) -> Self {
+        Self::new_from_state((seed ^ MULTIPLIER) & MASK)
+    }
+
+    /// Creates a generator from its internal 48 bit state,
+    /// without the scrambling `new` does.
+    pub fn new_from_state(state: u64) -> Self {
+        Self { seed: state & MASK }
+    }
+
+    /// Same as Java's protected `next(bits)`.
+    pub fn next_bits(&mut self, bits: u32) -> u32 {
+        self.seed = step(self.seed);
+        (self.seed >> (48 - bits)) as u32
+    }
+
+    /// Same as Java's `nextInt()`.
+    pub fn next_int(&mut self) -> i32 {
+        self.next_bits(32) as i32
+    }
+}
+
+fn step(state: u64) -> u64 {
+    state.wrapping_mul(MULTIPLIER).wrapping_add(ADDEND) & MASK
+}
+
+/// Recovers a `JavaRandom` from two consecutive `nextInt()` outputs.
+/// The returned generator continues right after the second output.
+pub fn recover_from_outputs(first: i32, second: i32) -> Option<JavaRandom> {
+    // Each output is the top 32 bits of the state, so after the
+    // first one only the 16 lowest bits are unknown. Just try all
+    // of them and keep the one that gives us the second output.
+    let high = (first as u32 as u64) << 16;
+    (0..1 << 16)
+        .map(|low| step(high | low))
+        .find(|state| (state >> 16) as u32 == second as u32)
+        .map(JavaRandom::new_from_state)
+}
+
+impl Random for JavaRandom {
+    fn in_range(&mut self, floor: usize, ceiling: usize) -> Result<usize> {
+        bounded(floor, ceiling, 32, || Ok(self.next().unwrap() as u64))
+    }
+
+    fn flip_coin(&mut self) -> Result<bool> {
+        Ok(self.in_range(0, 2)? == 0)
+    }
+
+    fn get(&mut self, bytes: usize) -> Result<Vec<usize>> {
+        Ok(self.take(bytes).collect())
+    }
+}
+
+impl Iterator for JavaRandom {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        Some(self.next_bits(32) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_java_random() {
+        // new Random(0).nextInt(), twice
+        let mut random = JavaRandom::new(0);
+        assert_eq!(random.next_int(), -1155484576);
+        assert_eq!(random.next_int(), -723955400);
+    }
+
+    #[test]
+    fn test_recover_from_outputs() {
+        let mut original = JavaRandom::new(0xdeadbeef);
+        let first = original.next_int();
+        let second = original.next_int();
+
+        let mut recovered = recover_from_outputs(first, second).unwrap();
+        for _ in 0..100 {
+            assert_eq!(recovered.next_int(), original.next_int());
+        }
+    }
+}
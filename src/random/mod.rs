@@ -1,30 +1,97 @@
+pub mod glibc;
+pub mod java;
+pub mod pcg;
+pub mod stats;
+pub mod tokens;
+pub mod xorshift;
+
+use std::cell::RefCell;
 use std::fs::File;
 use std::io::{Error, Read};
 use std::{error, result};
 
 type Result<T> = result::Result<T, Box<dyn error::Error>>;
 
+thread_local! {
+    /// The source the free functions of this module draw from.
+    /// When empty, they fall back to a fresh `Urandom`.
+    static SOURCE: RefCell<Option<Box<dyn Random>>> = RefCell::new(None);
+}
+
 pub fn get_random(bytes: usize) -> Vec<u8> {
-    let mut source = Urandom::new().unwrap();
-    source
-        .get(bytes)
-        .unwrap()
+    draw(|source| source.get(bytes))
         .into_iter()
         .map(|b| b as u8)
         .collect()
 }
 
 pub fn in_range(floor: usize, ceiling: usize) -> usize {
-    let mut source = Urandom::new().unwrap();
-    source.in_range(floor, ceiling).unwrap()
+    draw(|source| source.in_range(floor, ceiling))
 }
 
 pub fn flip_coin() -> bool {
-    let mut source = Urandom::new().unwrap();
-    source.flip_coin().unwrap()
+    draw(|source| source.flip_coin())
+}
+
+/// Runs `f` with `get_random`, `in_range` and `flip_coin` drawing from
+/// a Mersenne Twister seeded with `seed` instead of /dev/urandom,
+/// so every key, IV and prefix generated inside can be replayed.
+pub fn with_rng<T>(seed: u32, f: impl FnOnce() -> T) -> T {
+    with_source(Box::new(MersenneTwister::new(seed)), f)
+}
+
+/// Like `with_rng`, but with an arbitrary source of randomness.
+/// Calls can be nested: the previous source is restored
+/// when `f` returns, even if it panics.
+pub fn with_source<T>(source: Box<dyn Random>, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<Box<dyn Random>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            SOURCE.with(|s| *s.borrow_mut() = previous);
+        }
+    }
+
+    let _restore = Restore(SOURCE.with(|s| s.replace(Some(source))));
+    f()
+}
+
+fn draw<T>(f: impl FnOnce(&mut dyn Random) -> Result<T>) -> T {
+    SOURCE.with(|s| match s.borrow_mut().as_mut() {
+        Some(source) => f(source.as_mut()).unwrap(),
+        None => f(&mut Urandom::new().unwrap()).unwrap(),
+    })
+}
+
+/// Draws uniformly from `floor..ceiling`, given a source of
+/// numbers of `bits` random bits. Numbers from the last, partial
+/// copy of the range are rejected, so low values aren't favoured.
+pub(crate) fn bounded(
+    floor: usize,
+    ceiling: usize,
+    bits: u32,
+    mut next: impl FnMut() -> Result<u64>,
+) -> Result<usize> {
+    if ceiling <= floor {
+        return Err(format!("empty range {}..{}", floor, ceiling).into());
+    }
+    let range = (ceiling - floor) as u128;
+    let outputs = 1u128 << bits;
+    if range > outputs {
+        return Err(format!("range of {} is wider than {} bits", range, bits).into());
+    }
+    let limit = outputs - outputs % range;
+    loop {
+        let random = next()? as u128;
+        if random < limit {
+            return Ok(floor + (random % range) as usize);
+        }
+    }
 }
 
 pub trait Random {
+    /// A number in `floor..ceiling`.
     fn in_range(&mut self, floor: usize, ceiling: usize) -> Result<usize>;
     fn flip_coin(&mut self) -> Result<bool>;
     fn get(&mut self, bytes: usize) -> Result<Vec<usize>>;
@@ -44,8 +111,11 @@ impl Urandom {
 
 impl Random for Urandom {
     fn in_range(&mut self, floor: usize, ceiling: usize) -> Result<usize> {
-        let random = self.get(1)?[0];
-        Ok((random as usize + floor).rem_euclid(floor + ceiling))
+        bounded(floor, ceiling, 32, || {
+            let mut bytes = [0; 4];
+            self.file.read_exact(&mut bytes)?;
+            Ok(u32::from_le_bytes(bytes) as u64)
+        })
     }
 
     fn flip_coin(&mut self) -> Result<bool> {
@@ -59,6 +129,7 @@ impl Random for Urandom {
     }
 }
 
+#[derive(Clone)]
 pub struct MersenneTwister {
     n: usize, // degree
     m: usize, // middle word, 1 <= m < n
@@ -180,8 +251,7 @@ impl MersenneTwister {
 
 impl Random for MersenneTwister {
     fn in_range(&mut self, floor: usize, ceiling: usize) -> Result<usize> {
-        let random = self.next().unwrap();
-        Ok((random + floor).rem_euclid(floor + ceiling))
+        bounded(floor, ceiling, 32, || Ok(self.next().unwrap() as u64))
     }
 
     fn flip_coin(&mut self) -> Result<bool> {
@@ -236,4 +306,47 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_bounded() {
+        let mut counter = 0u64..;
+        let mut next = || Ok(counter.next().unwrap());
+        assert_eq!(bounded(5, 10, 32, &mut next).unwrap(), 5);
+        assert_eq!(bounded(5, 10, 32, &mut next).unwrap(), 6);
+        // 0, 1 and 2 are a whole copy of the range, 3 is rejected
+        let mut outputs = vec![3u64, 2].into_iter();
+        assert_eq!(bounded(0, 3, 2, || Ok(outputs.next().unwrap())).unwrap(), 2);
+        // wider than the source, or empty
+        assert!(bounded(0, 9, 3, &mut next).is_err());
+        assert!(bounded(4, 4, 32, &mut next).is_err());
+        // doesn't overflow with numbers of 64 bits
+        assert_eq!(bounded(1, 3, 64, || Ok(u64::MAX - 1)).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_in_range() {
+        let mut mt = MersenneTwister::new(7);
+        for _ in 0..1000 {
+            let random = mt.in_range(5, 10).unwrap();
+            assert!((5..10).contains(&random), "{}", random);
+        }
+    }
+
+    #[test]
+    fn test_with_rng_is_reproducible() {
+        let draw = || (get_random(16), in_range(5, 10), flip_coin());
+        assert_eq!(with_rng(42, draw), with_rng(42, draw));
+        assert_ne!(with_rng(42, draw).0, with_rng(43, draw).0);
+    }
+
+    #[test]
+    fn test_with_rng_restores_previous_source() {
+        let (outer, inner, after) = with_rng(1, || {
+            let outer = get_random(4);
+            let inner = with_rng(2, || get_random(4));
+            (outer, inner, get_random(4))
+        });
+        assert_eq!(inner, with_rng(2, || get_random(4)));
+        assert_eq!([outer, after].concat(), with_rng(1, || get_random(8)));
+    }
 }
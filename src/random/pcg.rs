@@ -0,0 +1,77 @@
+//! PCG32, for use as a keystream or a randomness source.
+//! Unlike the other generators of this module there is no state
+//! recovery here: each output drops the low 27 bits of the state
+//! and is rotated by an amount taken from the top 5, so getting the
+//! state back takes lattice reduction or a search of 2^32 or more,
+//! which is out of scope.
+use super::{bounded, Random, Result};
+
+const MULTIPLIER: u64 = 6364136223846793005;
+
+/// PCG32 (XSH RR), the reference 32 bit generator of the PCG family.
+#[derive(Clone)]
+pub struct Pcg32 {
+    state: u64,
+    increment: u64,
+}
+
+impl Pcg32 {
+    /// Same as `pcg32_srandom_r(rng, seed, sequence)`.
+    pub fn new(seed: u64, sequence: u64) -> Self {
+        let mut pcg = Self {
+            state: 0,
+            increment: (sequence << 1) | 1,
+        };
+        pcg.next_u32();
+        pcg.state = pcg.state.wrapping_add(seed);
+        pcg.next_u32();
+        pcg
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(self.increment);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rotation = (old >> 59) as u32;
+        xorshifted.rotate_right(rotation)
+    }
+}
+
+impl Random for Pcg32 {
+    fn in_range(&mut self, floor: usize, ceiling: usize) -> Result<usize> {
+        bounded(floor, ceiling, 32, || Ok(self.next().unwrap() as u64))
+    }
+
+    fn flip_coin(&mut self) -> Result<bool> {
+        Ok(self.in_range(0, 2)? == 0)
+    }
+
+    fn get(&mut self, bytes: usize) -> Result<Vec<usize>> {
+        Ok(self.take(bytes).collect())
+    }
+}
+
+impl Iterator for Pcg32 {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        Some(self.next_u32() as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pcg32() {
+        // from pcg32-demo, seeded with 42u, 54u
+        let mut pcg = Pcg32::new(42, 54);
+        let expected = [
+            0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e,
+        ];
+        for e in expected.iter() {
+            assert_eq!(pcg.next_u32(), *e);
+        }
+    }
+}
@@ -0,0 +1,422 @@
+use super::Random;
+use std::f64::consts::{LN_2, SQRT_2};
+use std::fmt;
+
+// A subset of the NIST SP 800-22 statistical tests for
+// random number generators. Every test gives a p-value:
+// the probability of a perfect random generator producing
+// a sequence less random than the one tested. NIST considers
+// a sequence random if the p-value is >= 0.01.
+// Sequences are slices of bits, each one being either 0 or 1.
+// [1] https://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-22r1a.pdf
+
+/// The significance level NIST recommends.
+pub const ALPHA: f64 = 0.01;
+
+/// Unpacks bytes into bits, most significant first.
+pub fn bits_from_bytes(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1))
+        .collect()
+}
+
+/// Draws `bytes` bytes from the source and unpacks them into bits.
+pub fn bits_from_random(source: &mut dyn Random, bytes: usize) -> Vec<u8> {
+    let random: Vec<u8> = source
+        .get(bytes)
+        .unwrap()
+        .into_iter()
+        .map(|b| b as u8)
+        .collect();
+    bits_from_bytes(&random)
+}
+
+/// Frequency (monobit) test, section 2.1.
+/// Are there as many ones as zeroes?
+pub fn monobit(bits: &[u8]) -> f64 {
+    let n = bits.len() as f64;
+    let sum: f64 = bits.iter().map(|&b| 2.0 * b as f64 - 1.0).sum();
+    erfc(sum.abs() / n.sqrt() / SQRT_2)
+}
+
+/// Frequency test within a block, section 2.2.
+/// Are there as many ones as zeroes in every `m` bits long block?
+/// Panics unless there is at least one block, so `1 <= m <= bits.len()`.
+pub fn block_frequency(bits: &[u8], m: usize) -> f64 {
+    assert!(
+        m >= 1 && m <= bits.len(),
+        "block length {} out of range for {} bits",
+        m,
+        bits.len()
+    );
+    let blocks = bits.len() / m;
+    let chi_squared: f64 = bits
+        .chunks_exact(m)
+        .map(|block| {
+            let proportion = block.iter().filter(|&&b| b == 1).count() as f64 / m as f64;
+            (proportion - 0.5).powi(2)
+        })
+        .sum::<f64>()
+        * 4.0
+        * m as f64;
+    igamc(blocks as f64 / 2.0, chi_squared / 2.0)
+}
+
+/// Runs test, section 2.3.
+/// Do ones and zeroes alternate as fast as they should?
+pub fn runs(bits: &[u8]) -> f64 {
+    let n = bits.len() as f64;
+    let pi = bits.iter().filter(|&&b| b == 1).count() as f64 / n;
+    // the test is pointless if monobit would
+    // have already failed the sequence
+    if (pi - 0.5).abs() >= 2.0 / n.sqrt() {
+        return 0.0;
+    }
+    let runs = 1 + bits.windows(2).filter(|w| w[0] != w[1]).count();
+    let expected = 2.0 * n * pi * (1.0 - pi);
+    erfc((runs as f64 - expected).abs() / (2.0 * (2.0 * n).sqrt() * pi * (1.0 - pi)))
+}
+
+/// Serial test, section 2.11.
+/// Are all the `m` bits long patterns equally likely?
+/// Returns the two p-values of the test.
+/// Panics if `m < 3`, the second p-value needs patterns of `m - 2` bits.
+pub fn serial(bits: &[u8], m: usize) -> (f64, f64) {
+    assert!(m >= 3, "pattern length {} is less than 3", m);
+    let psi_m = psi_squared(bits, m);
+    let psi_m1 = psi_squared(bits, m - 1);
+    let psi_m2 = psi_squared(bits, m - 2);
+    let delta = psi_m - psi_m1;
+    let delta_squared = psi_m - 2.0 * psi_m1 + psi_m2;
+    (
+        igamc(2f64.powi(m as i32 - 2), delta / 2.0),
+        igamc(2f64.powi(m as i32 - 3), delta_squared / 2.0),
+    )
+}
+
+/// Approximate entropy test, section 2.12.
+/// Are `m` and `m + 1` bits long patterns as frequent as they should?
+pub fn approximate_entropy(bits: &[u8], m: usize) -> f64 {
+    let n = bits.len() as f64;
+    let phi = |m: usize| -> f64 {
+        pattern_counts(bits, m)
+            .into_iter()
+            .filter(|&count| count > 0)
+            .map(|count| {
+                let c = count as f64 / n;
+                c * c.ln()
+            })
+            .sum()
+    };
+    let apen = phi(m) - phi(m + 1);
+    let chi_squared = 2.0 * n * (LN_2 - apen);
+    igamc(2f64.powi(m as i32 - 1), chi_squared / 2.0)
+}
+
+/// Cumulative sums test, section 2.13.
+/// Does the random walk made by the bits stray too far from zero?
+/// Returns the p-values going forward and backward.
+pub fn cumulative_sums(bits: &[u8]) -> (f64, f64) {
+    let backward: Vec<u8> = bits.iter().rev().copied().collect();
+    (cusum(bits), cusum(&backward))
+}
+
+fn cusum(bits: &[u8]) -> f64 {
+    let n = bits.len() as f64;
+    let mut sum: i64 = 0;
+    let mut z: i64 = 0;
+    for &b in bits {
+        sum += 2 * b as i64 - 1;
+        z = z.max(sum.abs());
+    }
+    let z = z as f64;
+
+    let phi = |k: i64, offset: i64| normal_cdf((4 * k + offset) as f64 * z / n.sqrt());
+    let mut p = 1.0;
+    for k in ((-n / z + 1.0) / 4.0) as i64..=((n / z - 1.0) / 4.0) as i64 {
+        p -= phi(k, 1) - phi(k, -1);
+    }
+    for k in ((-n / z - 3.0) / 4.0) as i64..=((n / z - 1.0) / 4.0) as i64 {
+        p += phi(k, 3) - phi(k, 1);
+    }
+    p
+}
+
+/// ψ² statistic for the serial test.
+fn psi_squared(bits: &[u8], m: usize) -> f64 {
+    if m == 0 {
+        return 0.0;
+    }
+    let n = bits.len() as f64;
+    let sum: f64 = pattern_counts(bits, m)
+        .into_iter()
+        .map(|count| (count * count) as f64)
+        .sum();
+    2f64.powi(m as i32) / n * sum - n
+}
+
+/// Counts every overlapping `m` bits long pattern,
+/// wrapping around at the end of the sequence.
+fn pattern_counts(bits: &[u8], m: usize) -> Vec<usize> {
+    let mut counts = vec![0; 1 << m];
+    for i in 0..bits.len() {
+        let pattern = (0..m).fold(0, |acc, j| acc << 1 | bits[(i + j) % bits.len()] as usize);
+        counts[pattern] += 1;
+    }
+    counts
+}
+
+/// The results of running every test.
+pub struct Report {
+    pub monobit: f64,
+    pub block_frequency: f64,
+    pub runs: f64,
+    pub serial: (f64, f64),
+    pub approximate_entropy: f64,
+    pub cumulative_sums: (f64, f64),
+}
+
+impl Report {
+    /// The fewest bits the report is made of: one block
+    /// of the block frequency test.
+    pub const MIN_BITS: usize = 128;
+
+    /// Runs every test over the bits.
+    /// Uses 128 bits long blocks for the block frequency test
+    /// and 4 bits long patterns for serial and approximate entropy.
+    /// None if there are fewer than `MIN_BITS` bits.
+    pub fn new(bits: &[u8]) -> Option<Self> {
+        if bits.len() < Self::MIN_BITS {
+            return None;
+        }
+        Some(Self {
+            monobit: monobit(bits),
+            block_frequency: block_frequency(bits, 128),
+            runs: runs(bits),
+            serial: serial(bits, 4),
+            approximate_entropy: approximate_entropy(bits, 4),
+            cumulative_sums: cumulative_sums(bits),
+        })
+    }
+
+    /// None if there are fewer than 16 bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Self::new(&bits_from_bytes(bytes))
+    }
+
+    /// None if `bytes` is less than 16.
+    pub fn from_random(source: &mut dyn Random, bytes: usize) -> Option<Self> {
+        Self::new(&bits_from_random(source, bytes))
+    }
+
+    fn p_values(&self) -> [(&str, f64); 8] {
+        [
+            ("monobit", self.monobit),
+            ("block frequency", self.block_frequency),
+            ("runs", self.runs),
+            ("serial (1)", self.serial.0),
+            ("serial (2)", self.serial.1),
+            ("approximate entropy", self.approximate_entropy),
+            ("cumulative sums (forward)", self.cumulative_sums.0),
+            ("cumulative sums (backward)", self.cumulative_sums.1),
+        ]
+    }
+
+    /// True if every p-value is at least `alpha`.
+    pub fn passes(&self, alpha: f64) -> bool {
+        self.p_values().iter().all(|(_, p)| *p >= alpha)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, p) in self.p_values().iter() {
+            let verdict = if *p >= ALPHA { "PASS" } else { "FAIL" };
+            writeln!(f, "{:<28} {:.6} {}", name, p, verdict)?;
+        }
+        Ok(())
+    }
+}
+
+/// The complementary error function.
+fn erfc(x: f64) -> f64 {
+    if x < 0.0 {
+        2.0 - igamc(0.5, x * x)
+    } else {
+        igamc(0.5, x * x)
+    }
+}
+
+/// The cumulative distribution function of the standard normal.
+fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / SQRT_2)
+}
+
+/// The regularized upper incomplete gamma function, Q(a, x).
+/// Numerical Recipes, section 6.2: a series when x is small,
+/// a continued fraction otherwise.
+fn igamc(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    if x < a + 1.0 {
+        1.0 - igam_series(a, x)
+    } else {
+        igamc_continued_fraction(a, x)
+    }
+}
+
+const EPSILON: f64 = 1e-15;
+const TINY: f64 = 1e-300;
+
+fn igam_series(a: f64, x: f64) -> f64 {
+    let mut ap = a;
+    let mut delta = 1.0 / a;
+    let mut sum = delta;
+    for _ in 0..1000 {
+        ap += 1.0;
+        delta *= x / ap;
+        sum += delta;
+        if delta.abs() < sum.abs() * EPSILON {
+            break;
+        }
+    }
+    sum * (-x + a * x.ln() - ln_gamma(a)).exp()
+}
+
+fn igamc_continued_fraction(a: f64, x: f64) -> f64 {
+    // modified Lentz's method
+    let mut b = x + 1.0 - a;
+    let mut c = 1.0 / TINY;
+    let mut d = 1.0 / b;
+    let mut h = d;
+    for i in 1..1000 {
+        let an = -(i as f64) * (i as f64 - a);
+        b += 2.0;
+        d = an * d + b;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = b + an / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    (-x + a * x.ln() - ln_gamma(a)).exp() * h
+}
+
+/// ln(Γ(x)) through the Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut series = 1.000000000190015;
+    for (i, c) in COEFFICIENTS.iter().enumerate() {
+        series += c / (x + 1.0 + i as f64);
+    }
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::MersenneTwister;
+
+    // The examples are the ones worked out in the NIST paper.
+
+    fn bits(s: &str) -> Vec<u8> {
+        s.bytes().map(|b| b - b'0').collect()
+    }
+
+    fn assert_close(got: f64, expected: f64) {
+        assert!((got - expected).abs() < 1e-6, "{} != {}", got, expected);
+    }
+
+    #[test]
+    fn test_monobit() {
+        assert_close(monobit(&bits("1011010101")), 0.527089);
+    }
+
+    #[test]
+    fn test_block_frequency() {
+        assert_close(block_frequency(&bits("0110011010"), 3), 0.801252);
+    }
+
+    #[test]
+    #[should_panic(expected = "block length 0")]
+    fn test_block_frequency_empty_blocks() {
+        block_frequency(&bits("0110011010"), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "block length 11")]
+    fn test_block_frequency_no_block() {
+        block_frequency(&bits("0110011010"), 11);
+    }
+
+    #[test]
+    fn test_runs() {
+        assert_close(runs(&bits("1001101011")), 0.147232);
+    }
+
+    #[test]
+    fn test_serial() {
+        let (p1, p2) = serial(&bits("0011011101"), 3);
+        assert_close(p1, 0.808792);
+        assert_close(p2, 0.670320);
+    }
+
+    #[test]
+    fn test_serial_short_patterns() {
+        for m in 0..3 {
+            let result = std::panic::catch_unwind(|| serial(&bits("0011011101"), m));
+            assert!(result.is_err(), "m = {}", m);
+        }
+    }
+
+    #[test]
+    fn test_approximate_entropy() {
+        assert_close(approximate_entropy(&bits("0100110101"), 3), 0.261961);
+    }
+
+    #[test]
+    fn test_cumulative_sums() {
+        assert_close(cumulative_sums(&bits("1011010111")).0, 0.4116588);
+    }
+
+    #[test]
+    fn test_mersenne_twister_passes() {
+        let report = Report::from_random(&mut MersenneTwister::new(5489), 10_000).unwrap();
+        assert!(report.passes(ALPHA), "\n{}", report);
+    }
+
+    #[test]
+    fn test_counter_fails() {
+        let counter: Vec<u8> = (0..10_000).map(|i| i as u8).collect();
+        let report = Report::from_bytes(&counter).unwrap();
+        assert!(!report.passes(ALPHA), "\n{}", report);
+    }
+
+    #[test]
+    fn test_report_too_short() {
+        assert!(Report::from_bytes(&[]).is_none());
+        assert!(Report::from_bytes(&[1, 2, 3]).is_none());
+        assert!(Report::from_bytes(&[0x55; 15]).is_none());
+        assert!(Report::from_bytes(&[0x55; 16]).is_some());
+        assert!(Report::from_random(&mut MersenneTwister::new(1), 15).is_none());
+    }
+}
@@ -0,0 +1,91 @@
+use super::{MersenneTwister, Random};
+use crate::encoding::hex::{self, Case};
+use crate::symm::prng::{self, PRNG};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Generates a password reset token, `bytes` long and hex encoded,
+/// from the keystream of the given PRNG.
+pub fn generate<P: PRNG + Clone>(prng: P, bytes: usize) -> String {
+    let keystream = prng::Cipher::new(prng).keystream(bytes);
+    hex::encode(&keystream, Case::Lower)
+}
+
+/// Generates a token the way too many services do:
+/// from a Mersenne Twister seeded with the current time.
+pub fn generate_from_time(bytes: usize) -> String {
+    generate(MersenneTwister::new(now()), bytes)
+}
+
+/// Generates a token straight from /dev/urandom.
+pub fn generate_from_urandom(bytes: usize) -> String {
+    let random: Vec<u8> = super::Urandom::new()
+        .and_then(|mut urandom| urandom.get(bytes))
+        .unwrap()
+        .into_iter()
+        .map(|b| b as u8)
+        .collect();
+    hex::encode(&random, Case::Lower)
+}
+
+/// Tells whether the token came from a Mersenne Twister seeded
+/// with a timestamp from the last `window`.
+pub fn is_mt_token_from_recent_time(token: &str, window: Duration) -> bool {
+    find_mt_token_seed(token, window).is_some()
+}
+
+/// Returns the timestamp the Mersenne Twister which generated the token
+/// was seeded with, if it is from the last `window`.
+pub fn find_mt_token_seed(token: &str, window: Duration) -> Option<u32> {
+    let bytes = token.len() / 2;
+    let token = token.to_ascii_lowercase();
+    let now = now();
+    let oldest = now.saturating_sub(window.as_secs() as u32);
+
+    // Timestamps are the worst possible seed: there's just a
+    // handful of them to try. Go backwards as recent is likelier.
+    (oldest..=now)
+        .rev()
+        .find(|&seed| generate(MersenneTwister::new(seed), bytes) == token)
+}
+
+fn now() -> u32 {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards");
+    (since_epoch.as_secs() % u32::MAX as u64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_token_from_time() {
+        let token = generate_from_time(16);
+        assert_eq!(token.len(), 32);
+        assert!(is_mt_token_from_recent_time(
+            &token,
+            Duration::from_secs(60)
+        ));
+    }
+
+    #[test]
+    fn test_detect_token_from_earlier_time() {
+        let seed = now() - 30;
+        let token = generate(MersenneTwister::new(seed), 16);
+        assert_eq!(
+            find_mt_token_seed(&token, Duration::from_secs(60)),
+            Some(seed)
+        );
+        assert_eq!(find_mt_token_seed(&token, Duration::from_secs(10)), None);
+    }
+
+    #[test]
+    fn test_urandom_token_is_not_detected() {
+        let token = generate_from_urandom(16);
+        assert!(!is_mt_token_from_recent_time(
+            &token,
+            Duration::from_secs(60)
+        ));
+    }
+}
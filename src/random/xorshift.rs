@@ -0,0 +1,167 @@
+use super::{bounded, Random, Result};
+
+/// xorshift128+, the generator that was behind `Math.random()`
+/// in the main browsers for a while.
+#[derive(Clone)]
+pub struct Xorshift128Plus {
+    state: [u64; 2],
+}
+
+impl Xorshift128Plus {
+    /// The state must not be all zeroes, or
+    /// the generator will only output zeroes.
+    pub fn new(state: [u64; 2]) -> Self {
+        Self { state }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut s1 = self.state[0];
+        let s0 = self.state[1];
+        self.state[0] = s0;
+        s1 ^= s1 << 23;
+        s1 ^= s1 >> 17;
+        s1 ^= s0;
+        s1 ^= s0 >> 26;
+        self.state[1] = s1;
+        s1.wrapping_add(s0)
+    }
+}
+
+/// A 64 bit word where each bit is not known, but is
+/// the XOR of some bits of the initial 128 bit state.
+/// Bit `i` of the word is described by the mask at position `i`.
+type Symbolic = [u128; 64];
+
+fn shift_left(word: &Symbolic, n: usize) -> Symbolic {
+    let mut shifted = [0; 64];
+    shifted[n..].copy_from_slice(&word[..64 - n]);
+    shifted
+}
+
+fn shift_right(word: &Symbolic, n: usize) -> Symbolic {
+    let mut shifted = [0; 64];
+    shifted[..64 - n].copy_from_slice(&word[n..]);
+    shifted
+}
+
+fn xor(word: &Symbolic, other: &Symbolic) -> Symbolic {
+    let mut xored = *word;
+    for (bit, other_bit) in xored.iter_mut().zip(other.iter()) {
+        *bit ^= other_bit
+    }
+    xored
+}
+
+/// Recovers the generator from its outputs, which must be
+/// consecutive and at least 128 long. Those are the raw 64 bit
+/// outputs of `next_u64`, not the doubles a browser would make
+/// of them, which keep other bits and may come out of order.
+/// The returned generator continues right after the last output.
+pub fn recover_from_outputs(outputs: &[u64]) -> Option<Xorshift128Plus> {
+    // The addition at the end is the only non-linear part of the
+    // generator, but the lowest bit of a sum is just the XOR of
+    // the lowest bits. So every output gives us a linear equation
+    // over GF(2) on the 128 bits of the initial state: replay the
+    // generator symbolically, then solve the system.
+    let mut a: Symbolic = [0; 64];
+    let mut b: Symbolic = [0; 64];
+    for i in 0..64 {
+        a[i] = 1 << i;
+        b[i] = 1 << (64 + i);
+    }
+
+    let mut equations = Vec::with_capacity(outputs.len());
+    for output in outputs {
+        let mut s1 = a;
+        let s0 = b;
+        a = s0;
+        s1 = xor(&s1, &shift_left(&s1, 23));
+        s1 = xor(&s1, &shift_right(&s1, 17));
+        s1 = xor(&s1, &s0);
+        s1 = xor(&s1, &shift_right(&s0, 26));
+        b = s1;
+        equations.push((s1[0] ^ s0[0], output & 1 == 1));
+    }
+
+    let solution = solve(equations)?;
+    let mut recovered = Xorshift128Plus::new([solution as u64, (solution >> 64) as u64]);
+    for _ in outputs {
+        recovered.next_u64();
+    }
+    Some(recovered)
+}
+
+/// Gauss-Jordan elimination over GF(2) for 128 unknowns.
+/// Returns None if the system does not have a single solution.
+fn solve(mut equations: Vec<(u128, bool)>) -> Option<u128> {
+    let mut solution = 0;
+    let mut rank = 0;
+    for bit in 0..128 {
+        let pivot = (rank..equations.len()).find(|&i| equations[i].0 >> bit & 1 == 1)?;
+        equations.swap(rank, pivot);
+        let (mask, value) = equations[rank];
+        for (i, equation) in equations.iter_mut().enumerate() {
+            if i != rank && equation.0 >> bit & 1 == 1 {
+                equation.0 ^= mask;
+                equation.1 ^= value;
+            }
+        }
+        rank += 1;
+    }
+    // Leftover equations must be all zeroes, or the outputs
+    // did not come from the generator.
+    if equations[rank..].iter().any(|&(_, value)| value) {
+        return None;
+    }
+    for (bit, &(_, value)) in equations[..rank].iter().enumerate() {
+        if value {
+            solution |= 1 << bit;
+        }
+    }
+    Some(solution)
+}
+
+impl Random for Xorshift128Plus {
+    fn in_range(&mut self, floor: usize, ceiling: usize) -> Result<usize> {
+        bounded(floor, ceiling, 64, || Ok(self.next().unwrap() as u64))
+    }
+
+    fn flip_coin(&mut self) -> Result<bool> {
+        Ok(self.in_range(0, 2)? == 0)
+    }
+
+    fn get(&mut self, bytes: usize) -> Result<Vec<usize>> {
+        Ok(self.take(bytes).collect())
+    }
+}
+
+impl Iterator for Xorshift128Plus {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        Some(self.next_u64() as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recover_from_outputs() {
+        let mut original = Xorshift128Plus::new([0x0123456789abcdef, 0xfedcba9876543210]);
+        let outputs: Vec<u64> = (0..200).map(|_| original.next_u64()).collect();
+
+        let mut recovered = recover_from_outputs(&outputs).unwrap();
+        for _ in 0..100 {
+            assert_eq!(recovered.next_u64(), original.next_u64());
+        }
+    }
+
+    #[test]
+    fn test_recover_not_enough_outputs() {
+        let mut original = Xorshift128Plus::new([1, 2]);
+        let outputs: Vec<u64> = (0..64).map(|_| original.next_u64()).collect();
+        assert!(recover_from_outputs(&outputs).is_none());
+    }
+}
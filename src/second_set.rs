@@ -1,4 +1,4 @@
-use crypto::encoding::{base64, hex};
+use crypto::encoding::{base64, hex, urlencoded};
 use crypto::symm::padding::Padding;
 use crypto::symm::AESCiphertext;
 use crypto::{random, symm};
@@ -180,46 +180,29 @@ pub fn ecb_cut_and_paste() {
 
     impl User {
         pub fn new(data: &str) -> User {
-            let (mut email, mut uid, mut role): (String, String, String) =
-                ("".to_owned(), "".to_owned(), "".to_owned());
-            let attrs = data.split('&').map(|a| a.split('='));
-            for mut attr in attrs {
-                let key = match attr.next() {
-                    Some("") => continue,
-                    Some(s) => s.to_owned(),
-                    // It is surprisingly impossible as far as I can tell
-                    // to get a zero-length iterator from a split.
-                    None => unreachable!(),
-                };
-                let value = attr.next().map(|s| s.into()).unwrap_or(String::from(""));
-
-                if let Some(_) = attr.next() {
-                    panic!()
-                }
-
-                match key.as_ref() {
-                    "email" => email = value,
-                    "uid" => uid = value,
-                    "role" => role = value,
-                    _ => panic!(),
-                }
+            let params = urlencoded::decode(data).unwrap();
+            // like most frameworks, take the first value of repeated keys
+            let get = |key| params.get(key).unwrap_or_default().to_owned();
+            User {
+                email: get("email"),
+                uid: get("uid"),
+                role: get("role"),
             }
-
-            User { email, uid, role }
         }
 
         pub fn as_cookie(&self) -> String {
-            format!("email={}&uid={}&role={}", self.email, self.uid, self.role)
+            urlencoded::encode(vec![
+                ("email", &self.email),
+                ("uid", &self.uid),
+                ("role", &self.role),
+            ])
         }
     }
 
     let profile_for = |email: &str| -> String {
-        // Sanitize, what could go wrong?
-        let mut ok_email = email.replace("&", "");
-        ok_email = ok_email.replace("=", "");
-
+        // No need to sanitize, the encoding escapes & and =
         let user = User {
-            email: ok_email,
+            email: email.into(),
             uid: "10".into(),
             role: "user".into(),
         };
@@ -228,15 +211,13 @@ pub fn ecb_cut_and_paste() {
     let random_key = random::get_random(16);
 
     // "email=EMAIL&uid=UID&role=ROLE"
-    // we have to leave the string ADMIN in there somewhere
-    // use an email so that the `email=EMAIL` part is exactly
-    // 16 bytes, then set the rest to the string admin + padding
-    let email = "1".repeat(10);
-    let admin = "admin";
-    let pad =
-        &symm::padding::get_pad(&symm::padding::Padding::PKCS7, admin.as_bytes(), 16).unwrap();
-
-    let email = (email.to_owned() + admin) + str::from_utf8(&pad).unwrap();
+    // Everything but letters and a few symbols gets escaped now,
+    // so we can't sneak in the padding for a lone "admin" block.
+    // But repeated keys are allowed, and the first one wins.
+    // Use an email so that the `email=EMAIL` part is exactly
+    // 16 bytes and admin starts the second block:
+    // "email=1111111111" "admin&uid=10&rol" "e=user" + padding
+    let email = "1".repeat(10) + "admin";
     let profile = profile_for(&email);
     let first_cipher_text = AESCiphertext::new(
         &random_key,
@@ -245,11 +226,11 @@ pub fn ecb_cut_and_paste() {
         Padding::PKCS7,
     );
 
-    // now we have to leave the USER be by itself, so we can replace it
-    // with the second block of the first ciphertext
-    // without an email we have 23 bytes
-    // "email=&uid=10&role=user"
-    // we need to push it to exactly 36 bytes (so only the user word is in the third block)
+    // now we need the role= to end a block, so we can follow
+    // it with the last two blocks of the first ciphertext
+    // without an email we have 19 bytes up to the role
+    // "email=&uid=10&role="
+    // we need to push it to exactly 32 bytes
     let profile = profile_for(&"1".repeat(13));
     let second_cipher_text = AESCiphertext::new(
         &random_key,
@@ -258,11 +239,10 @@ pub fn ecb_cut_and_paste() {
         Padding::PKCS7,
     );
 
-    // the second block of the first cipher text
-    let admin = &first_cipher_text.bytes[16..32];
+    // "email=1111111111111&uid=10&role=admin&uid=10&role=user"
     let mut copy_pasted_buf = Vec::new();
     copy_pasted_buf.extend_from_slice(&second_cipher_text.bytes[0..32]);
-    copy_pasted_buf.extend_from_slice(admin);
+    copy_pasted_buf.extend_from_slice(&first_cipher_text.bytes[16..48]);
     let copy_pasted =
         AESCiphertext::from_existing(copy_pasted_buf, symm::Mode::ECB, Padding::PKCS7);
     let decrypted = copy_pasted.decrypt(&random_key);
@@ -338,7 +318,7 @@ pub fn byte_at_a_time_ecb_decryption_hard() {
     let padding = "X".repeat(48);
     let mut oracled = oracle(&padding);
     let (repeated_block, mut times) = get_repeated_in_ecb(&oracled).unwrap();
-    let position_of_repeated = crypto::position_of_block_in(&oracled, &repeated_block);
+    let position_of_repeated = crypto::position_of_block_in(&oracled, &repeated_block, 16);
 
     // Now the fun starts. I need to make my input block appear only one time.
     // Then I know I have figured out how many random bytes are there mixing
@@ -349,7 +329,7 @@ pub fn byte_at_a_time_ecb_decryption_hard() {
         random_size += 1;
         let padding = "X".repeat(48 - random_size);
         oracled = oracle(&padding);
-        times = crypto::count_block_in_ciphertext(&oracled, &repeated_block);
+        times = crypto::count_block_in_ciphertext(&oracled, &repeated_block, 16);
     }
 
     // Make note that I had to try one padding LESS than random
@@ -429,13 +409,11 @@ pub fn cbc_bitflipping_attacks() {
     let iv = random::get_random(16);
 
     let encrypt_cbc = |s: &str| -> Vec<u8> {
-        let mut ok_input = s.replace(";", "");
-        ok_input = ok_input.replace("=", "");
-
-        let first_chunk = "comment1=cooking%20MCs;userdata=".to_owned();
-        let last_chunk = ";comment2=%20like%20a%20pound%20of%20bacon";
-
-        let plain_text = first_chunk + &ok_input + last_chunk;
+        let plain_text = urlencoded::SEMICOLON.encode(vec![
+            ("comment1", "cooking MCs"),
+            ("userdata", s),
+            ("comment2", " like a pound of bacon"),
+        ]);
         let cipher_text = AESCiphertext::new(
             &random_key,
             plain_text.as_bytes(),
@@ -459,8 +437,8 @@ pub fn cbc_bitflipping_attacks() {
 
     // Now, the objective is to get to put an `;admin=true` inside
     // the ciphertext. This should not be possible, because
-    // the encrypt_cbc function will eat up both ; and =, so the
-    // best I can do is endup with something like admintrue
+    // the encrypt_cbc function will escape both ; and =, so the
+    // best I can do is endup with something like %3Badmin%3Dtrue
     //
     // I will somehow have to craft a ciphertext that decrypts to that
     // though.
@@ -492,34 +470,35 @@ pub fn cbc_bitflipping_attacks() {
     // ASCII(;) == 0x3b == 0b1110 1100
     // ASCII(=) == 0x3d == 0b1111 0100
     //
-    // I will start by introducing a ;, I will be happy with that
-    // So if instead of ; I will something close to it
-    // ASCII(:) == 0b1110 1000
-    let mut cipher_break = encrypt_cbc("aa:admin\x3ctrue");
+    // Only letters, digits and *-._ make it through unescaped,
+    // so the characters close to ; and = have to be digits
+    // ASCII(3) == 0x33, just the 0x08 bit away from ;
+    // ASCII(5) == 0x35, just the 0x08 bit away from =
+    let mut cipher_break = encrypt_cbc("aa3admin5true");
 
     // One kinda needs to know where the things is putting the input
     // at least roughly... In this case we can decrypt the output so that's easy
     //
-    // So, why XOR the first block with 1 works?
+    // So, why XOR the first block with 0x08 works?
     // Because this will get xored with the almost-plaintext before producing the final text
     // when decrypting in ECB
     //
-    // In practice, this means that I will keep all of the XOR except for the last bit,
+    // In practice, this means that I will keep all of the XOR except for that bit,
     // which will get flipped (hence, CBC bit flipping)
     //
     // When flipping before xoring, we will cause the XOR to produce a byte exactly
-    // one bit bigger. Why?
+    // 0x08 bigger. Why?
     //
     // Because we know what's the input. We know that CIPHER_BLOCK_1[18] XOR PLAIN_TEXT_2[18]
     // (where CIPHER_BLOCK_1 referes to CIPHER_BLOCK number 1 of course and [18] an example position)
-    // equals ":", which is 0b1110 1100. This means that both have the same bit at the last position
-    // By flipping one, we cause it return 1 when xoring, thus increasing it one bit.
+    // equals "3", which is 0b0011 0011. This means that both have the same 0x08 bit.
+    // By flipping it, we cause it return 1 when xoring, thus increasing it by 0x08.
     //
-    // If our input ended with one, 0b1110 1101, for example, we would know that CIPHER_BLOCK_1[X]
-    // XOR PLAIN_TEXT_2[X] are different. When flipping one, we would cause them to be the same.
-    // Thus, we would DECREASE one bit!
-    cipher_break[18] ^= 0b0000_0001;
-    cipher_break[24] ^= 0b0000_0001;
+    // If our input had that bit set, 0b0011 1011, for example, we would know that CIPHER_BLOCK_1[X]
+    // XOR PLAIN_TEXT_2[X] are different. When flipping it, we would cause them to be the same.
+    // Thus, we would DECREASE it!
+    cipher_break[18] ^= 0b0000_1000;
+    cipher_break[24] ^= 0b0000_1000;
     println!("{:?}", &cipher_break);
     let plain = symm::aes_decrypt(
         &random_key,
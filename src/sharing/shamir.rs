@@ -0,0 +1,264 @@
+//! Shamir's secret sharing over GF(2^8): every byte of the secret is the
+//! constant term of a random polynomial of degree `threshold - 1`, and
+//! every share has the values of all of them at some `x`. Any `threshold`
+//! shares pin the polynomials down; less say nothing about the secret.
+
+use crate::encoding::hex;
+use crate::math::gf256::{Gf256, Poly};
+use crate::random;
+use std::str::FromStr;
+use std::{error, fmt};
+
+type Result<T> = std::result::Result<T, ShamirError>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShamirError {
+    /// The threshold must be at least one and at most the number of shares.
+    InvalidThreshold {
+        threshold: u8,
+        shares: u8,
+    },
+    NotEnoughShares {
+        needed: u8,
+        got: usize,
+    },
+    /// Two different shares with the same x.
+    DuplicateShare(u8),
+    /// The shares are not from the same split.
+    MismatchedShares,
+    /// A serialized share which could not be parsed.
+    MalformedShare,
+}
+
+impl fmt::Display for ShamirError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShamirError::InvalidThreshold { threshold, shares } => {
+                write!(f, "invalid threshold {} for {} shares", threshold, shares)
+            }
+            ShamirError::NotEnoughShares { needed, got } => {
+                write!(f, "need {} shares, got {}", needed, got)
+            }
+            ShamirError::DuplicateShare(x) => write!(f, "duplicate share {}", x),
+            ShamirError::MismatchedShares => write!(f, "shares are from different splits"),
+            ShamirError::MalformedShare => write!(f, "malformed share"),
+        }
+    }
+}
+
+impl error::Error for ShamirError {}
+
+/// One share of a secret.
+/// Serialized as `threshold-x-hex` with `Display` and `FromStr`,
+/// or as bytes, the threshold and x first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Share {
+    pub threshold: u8,
+    /// Where the polynomials were evaluated, never 0.
+    pub x: u8,
+    pub y: Vec<u8>,
+}
+
+impl Share {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.threshold, self.x];
+        bytes.extend_from_slice(&self.y);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        match *bytes {
+            [threshold, x, ref y @ ..] if threshold > 0 && x > 0 => Ok(Share {
+                threshold,
+                x,
+                y: y.to_vec(),
+            }),
+            _ => Err(ShamirError::MalformedShare),
+        }
+    }
+}
+
+impl fmt::Display for Share {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}-{}",
+            self.threshold,
+            self.x,
+            hex::encode(&self.y, hex::Case::Lower)
+        )
+    }
+}
+
+impl FromStr for Share {
+    type Err = ShamirError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.trim().splitn(3, '-');
+        let mut next = || parts.next().ok_or(ShamirError::MalformedShare);
+        let threshold = next()?.parse().map_err(|_| ShamirError::MalformedShare)?;
+        let x = next()?.parse().map_err(|_| ShamirError::MalformedShare)?;
+        let y = hex::from_string(next()?).map_err(|_| ShamirError::MalformedShare)?;
+        let mut bytes = vec![threshold, x];
+        bytes.extend(y);
+        Share::from_bytes(&bytes)
+    }
+}
+
+/// Split the secret in `shares` shares, any `threshold` of which can
+/// put it back together. The coefficients come from `random`.
+pub fn split(secret: &[u8], threshold: u8, shares: u8) -> Result<Vec<Share>> {
+    if threshold == 0 || threshold > shares {
+        return Err(ShamirError::InvalidThreshold { threshold, shares });
+    }
+
+    let mut result: Vec<Share> = (1..=shares)
+        .map(|x| Share {
+            threshold,
+            x,
+            y: Vec::with_capacity(secret.len()),
+        })
+        .collect();
+    for &byte in secret {
+        let mut coefficients = vec![Gf256(byte)];
+        coefficients.extend(
+            random::get_random(threshold as usize - 1)
+                .into_iter()
+                .map(Gf256),
+        );
+        let poly = Poly::new(coefficients);
+        for share in result.iter_mut() {
+            share.y.push(poly.eval(Gf256(share.x)).0);
+        }
+    }
+    Ok(result)
+}
+
+/// Put the secret back together from at least `threshold` shares,
+/// by Lagrange interpolation at zero.
+pub fn combine(shares: &[Share]) -> Result<Vec<u8>> {
+    let first = shares
+        .first()
+        .ok_or(ShamirError::NotEnoughShares { needed: 1, got: 0 })?;
+    let mut distinct: Vec<&Share> = Vec::with_capacity(shares.len());
+    for share in shares {
+        if share.threshold != first.threshold || share.y.len() != first.y.len() {
+            return Err(ShamirError::MismatchedShares);
+        }
+        match distinct.iter().find(|s| s.x == share.x) {
+            Some(s) if s.y != share.y => return Err(ShamirError::DuplicateShare(share.x)),
+            Some(_) => continue,
+            None => distinct.push(share),
+        }
+    }
+    if distinct.len() < first.threshold as usize {
+        return Err(ShamirError::NotEnoughShares {
+            needed: first.threshold,
+            got: distinct.len(),
+        });
+    }
+
+    let distinct = &distinct[..first.threshold as usize];
+    Ok((0..first.y.len())
+        .map(|i| {
+            let points: Vec<(Gf256, Gf256)> = distinct
+                .iter()
+                .map(|s| (Gf256(s.x), Gf256(s.y[i])))
+                .collect();
+            // the x are distinct, so this can't fail
+            Poly::interpolate_at(&points, Gf256::ZERO).unwrap().0
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symm::{self, padding::Padding, AESCiphertext};
+
+    #[test]
+    fn test_split_and_combine() {
+        let secret = b"YELLOW SUBMARINE";
+        let shares = random::with_rng(7, || split(secret, 3, 5)).unwrap();
+        assert_eq!(shares.len(), 5);
+        for a in 0..5 {
+            for b in a + 1..5 {
+                for c in b + 1..5 {
+                    let some = [shares[a].clone(), shares[b].clone(), shares[c].clone()];
+                    assert_eq!(combine(&some).unwrap(), secret);
+                }
+            }
+        }
+        assert_eq!(combine(&shares).unwrap(), secret);
+        assert_eq!(
+            combine(&shares[..2]),
+            Err(ShamirError::NotEnoughShares { needed: 3, got: 2 })
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            split(b"secret", 4, 3),
+            Err(ShamirError::InvalidThreshold {
+                threshold: 4,
+                shares: 3
+            })
+        );
+        assert!(split(b"secret", 0, 3).is_err());
+
+        let shares = split(b"secret", 2, 3).unwrap();
+        let mut forged = shares[1].clone();
+        forged.y[0] ^= 1;
+        assert_eq!(
+            combine(&[shares[1].clone(), forged]),
+            Err(ShamirError::DuplicateShare(2))
+        );
+        // the same share twice is only one
+        assert_eq!(
+            combine(&[shares[0].clone(), shares[0].clone()]),
+            Err(ShamirError::NotEnoughShares { needed: 2, got: 1 })
+        );
+        let other = split(b"other secret", 2, 3).unwrap();
+        assert_eq!(
+            combine(&[shares[0].clone(), other[1].clone()]),
+            Err(ShamirError::MismatchedShares)
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let share = Share {
+            threshold: 3,
+            x: 2,
+            y: vec![0xde, 0xad, 0xbe, 0xef],
+        };
+        assert_eq!(share.to_string(), "3-2-deadbeef");
+        assert_eq!("3-2-deadbeef".parse(), Ok(share.clone()));
+        assert_eq!(Share::from_bytes(&share.to_bytes()), Ok(share));
+        assert_eq!(
+            "3-0-deadbeef".parse::<Share>(),
+            Err(ShamirError::MalformedShare)
+        );
+        assert_eq!(
+            "3-2-nothex".parse::<Share>(),
+            Err(ShamirError::MalformedShare)
+        );
+        assert_eq!(Share::from_bytes(&[3]), Err(ShamirError::MalformedShare));
+    }
+
+    #[test]
+    fn test_share_aes_key() {
+        let key = random::get_random(16);
+        let cipher_text =
+            AESCiphertext::new(&key, b"launch codes", symm::Mode::ECB, Padding::PKCS7);
+        let shares: Vec<String> = split(&key, 2, 3)
+            .unwrap()
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let parsed: Vec<Share> = shares[1..].iter().map(|s| s.parse().unwrap()).collect();
+        let key = combine(&parsed).unwrap();
+        assert_eq!(cipher_text.decrypt(&key), b"launch codes");
+    }
+}
@@ -0,0 +1,91 @@
+//! Correlation power analysis: for every guess of a key byte, predict
+//! the Hamming weight of the first SubBytes output for each trace, and
+//! see how well that correlates with what was measured.
+use super::{hamming_weight, TraceSet};
+use crate::symm::aes::sbox;
+
+/// Pearson's correlation coefficient, 0 if either side is constant.
+pub fn correlation(xs: &[f64], ys: &[f64]) -> f64 {
+    let n = xs.len().min(ys.len()) as f64;
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+    let (mut covariance, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in xs.iter().zip(ys) {
+        covariance += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        return 0.0;
+    }
+    covariance / (var_x * var_y).sqrt()
+}
+
+/// For each guess of the key byte at `position`, the highest
+/// absolute correlation over every sample of the traces.
+pub fn scores(set: &TraceSet, position: usize) -> Vec<f64> {
+    let columns: Vec<Vec<f64>> = (0..set.samples()).map(|i| set.column(i)).collect();
+    (0..=255)
+        .map(|guess: u8| {
+            let hypothesis: Vec<f64> = set
+                .plaintexts()
+                .iter()
+                .map(|p| hamming_weight(sbox(p[position] ^ guess)) as f64)
+                .collect();
+            columns
+                .iter()
+                .map(|column| correlation(&hypothesis, column).abs())
+                .fold(0.0, f64::max)
+        })
+        .collect()
+}
+
+/// The key byte at `position` which correlates best, and its correlation.
+pub fn recover_byte(set: &TraceSet, position: usize) -> (u8, f64) {
+    best(&scores(set, position))
+}
+
+/// The whole AES key, byte by byte.
+pub fn cpa(set: &TraceSet) -> [u8; 16] {
+    let mut key = [0; 16];
+    for (position, byte) in key.iter_mut().enumerate() {
+        *byte = recover_byte(set, position).0;
+    }
+    key
+}
+
+/// The guess with the highest score.
+pub(super) fn best(scores: &[f64]) -> (u8, f64) {
+    scores
+        .iter()
+        .enumerate()
+        .fold((0, f64::MIN), |best, (guess, &score)| {
+            if score > best.1 {
+                (guess as u8, score)
+            } else {
+                best
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Device;
+    use super::*;
+    use crate::random;
+
+    #[test]
+    fn test_correlation() {
+        let xs = [1.0, 2.0, 3.0, 4.0];
+        assert!((correlation(&xs, &[2.0, 4.0, 6.0, 8.0]) - 1.0).abs() < 1e-12);
+        assert!((correlation(&xs, &[8.0, 6.0, 4.0, 2.0]) + 1.0).abs() < 1e-12);
+        assert_eq!(correlation(&xs, &[1.0; 4]), 0.0);
+    }
+
+    #[test]
+    fn test_cpa_recovers_key() {
+        let key = *b"YELLOW SUBMARINE";
+        let set = random::with_rng(7, || Device::new(&key, 1.0).trace_set(200));
+        assert_eq!(cpa(&set), key);
+    }
+}
@@ -0,0 +1,60 @@
+//! Difference of means power analysis, Kocher style: for every guess of
+//! a key byte, split the traces on one bit of the predicted first SubBytes
+//! output, and see how far apart the mean traces of both halves are.
+use super::cpa::best;
+use super::TraceSet;
+use crate::symm::aes::sbox;
+
+/// For each guess of the key byte at `position`, the largest difference
+/// of means over every sample, splitting on bit `bit` of the S-box output.
+pub fn scores(set: &TraceSet, position: usize, bit: u8) -> Vec<f64> {
+    (0..=255)
+        .map(|guess: u8| {
+            let mut sums = [vec![0.0; set.samples()], vec![0.0; set.samples()]];
+            let mut counts = [0usize; 2];
+            for (plaintext, trace) in set.plaintexts().iter().zip(set.traces()) {
+                let selected = ((sbox(plaintext[position] ^ guess) >> bit) & 1) as usize;
+                counts[selected] += 1;
+                for (sum, sample) in sums[selected].iter_mut().zip(trace) {
+                    *sum += sample;
+                }
+            }
+            if counts.contains(&0) {
+                return 0.0;
+            }
+            sums[0]
+                .iter()
+                .zip(&sums[1])
+                .map(|(zero, one)| (one / counts[1] as f64 - zero / counts[0] as f64).abs())
+                .fold(0.0, f64::max)
+        })
+        .collect()
+}
+
+/// The key byte at `position` with the largest difference of means, and that difference.
+pub fn recover_byte(set: &TraceSet, position: usize, bit: u8) -> (u8, f64) {
+    best(&scores(set, position, bit))
+}
+
+/// The whole AES key, byte by byte, splitting on bit `bit`.
+pub fn dpa(set: &TraceSet, bit: u8) -> [u8; 16] {
+    let mut key = [0; 16];
+    for (position, byte) in key.iter_mut().enumerate() {
+        *byte = recover_byte(set, position, bit).0;
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Device;
+    use super::*;
+    use crate::random;
+
+    #[test]
+    fn test_dpa_recovers_key() {
+        let key = *b"YELLOW SUBMARINE";
+        let set = random::with_rng(7, || Device::new(&key, 0.5).trace_set(1000));
+        assert_eq!(dpa(&set, 0), key);
+    }
+}
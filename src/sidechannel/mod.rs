@@ -0,0 +1,120 @@
+//! Power analysis against a simulated device.
+//!
+//! The device is `symm::aes::Cipher`, and what it leaks is the
+//! Hamming weight of each byte coming out of the first SubBytes,
+//! plus some Gaussian noise. That's enough to teach, and test,
+//! correlation and difference of means power analysis.
+pub mod cpa;
+pub mod dpa;
+pub mod traces;
+
+pub use cpa::cpa;
+pub use dpa::dpa;
+pub use traces::{TraceSet, TraceSetError};
+
+use crate::random;
+use crate::symm::aes::{Cipher, Step};
+use std::f64::consts::PI;
+
+/// The number of ones in a byte, which is what the
+/// simulated device leaks.
+pub fn hamming_weight(byte: u8) -> u8 {
+    byte.count_ones() as u8
+}
+
+/// A sample of a normal distribution with mean 0,
+/// drawn from `random` with the Box–Muller transform.
+pub fn gaussian(std_dev: f64) -> f64 {
+    // (0, 1], so the log never sees a 0
+    let uniform = || {
+        let bytes = random::get_random(4);
+        let n = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        (n as f64 + 1.0) / (u32::MAX as f64 + 1.0)
+    };
+    let (u1, u2) = (uniform(), uniform());
+    std_dev * (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+/// An AES device which leaks when encrypting.
+pub struct Device {
+    cipher: Cipher,
+    noise: f64,
+}
+
+impl Device {
+    /// `noise` is the standard deviation of the noise added to every sample.
+    pub fn new(key: &[u8], noise: f64) -> Self {
+        Self {
+            cipher: Cipher::new(key),
+            noise,
+        }
+    }
+
+    /// Encrypts `plaintext` and returns the ciphertext and the power trace,
+    /// one sample for each byte of the state after the first SubBytes.
+    pub fn encrypt(&mut self, plaintext: &[u8; 16]) -> ([u8; 16], Vec<f64>) {
+        self.cipher.start_tracing();
+        self.cipher.set_state(plaintext);
+        let ciphertext = self.cipher.encrypt();
+        let trace = self.cipher.take_trace().expect("tracing was started");
+        let sub_bytes = trace
+            .entries
+            .iter()
+            .find(|entry| entry.round == 1 && entry.step == Step::SubBytes)
+            .expect("every encryption has a first round");
+        let samples = sub_bytes
+            .state
+            .iter()
+            .map(|&b| hamming_weight(b) as f64 + gaussian(self.noise))
+            .collect();
+        (ciphertext, samples)
+    }
+
+    /// Encrypts `count` random plaintexts and records their traces.
+    pub fn trace_set(&mut self, count: usize) -> TraceSet {
+        let mut set = TraceSet::new(16);
+        for _ in 0..count {
+            let mut plaintext = [0; 16];
+            plaintext.copy_from_slice(&random::get_random(16));
+            let (_, samples) = self.encrypt(&plaintext);
+            set.push(plaintext, samples);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gaussian() {
+        let samples: Vec<f64> =
+            random::with_rng(1, || (0..10_000).map(|_| gaussian(2.0)).collect());
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance =
+            samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+        assert!(mean.abs() < 0.1, "mean {}", mean);
+        assert!(
+            (variance.sqrt() - 2.0).abs() < 0.1,
+            "std dev {}",
+            variance.sqrt()
+        );
+    }
+
+    #[test]
+    fn test_device_leaks_hamming_weight() {
+        let key = b"YELLOW SUBMARINE";
+        let plaintext = [0; 16];
+        let (ciphertext, samples) = Device::new(key, 0.0).encrypt(&plaintext);
+
+        let mut cipher = Cipher::new(key);
+        cipher.set_state(&plaintext);
+        assert_eq!(ciphertext, cipher.encrypt());
+        let expected: Vec<f64> = key
+            .iter()
+            .map(|&k| hamming_weight(crate::symm::aes::sbox(k)) as f64)
+            .collect();
+        assert_eq!(samples, expected);
+    }
+}
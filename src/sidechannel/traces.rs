@@ -0,0 +1,220 @@
+use std::convert::TryInto;
+use std::path::Path;
+use std::{error, fmt, fs, io};
+
+/// Trace sets start with these, so random files are not taken for one.
+const MAGIC: &[u8; 4] = b"TRCS";
+/// Bumped whenever the format changes.
+const VERSION: u8 = 1;
+
+/// Why some bytes could not be read as a trace set.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceSetError {
+    /// It doesn't start with the magic bytes, so it's not a trace set.
+    BadMagic,
+    /// A trace set from a newer version of the format.
+    UnsupportedVersion(u8),
+    /// The data ended early, or has bytes after the traces.
+    InvalidLength,
+}
+
+impl fmt::Display for TraceSetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TraceSetError::BadMagic => write!(f, "not a trace set"),
+            TraceSetError::UnsupportedVersion(v) => write!(f, "unsupported version {}", v),
+            TraceSetError::InvalidLength => write!(f, "truncated or oversized trace set"),
+        }
+    }
+}
+
+impl error::Error for TraceSetError {}
+
+/// Power traces and the plaintexts that were
+/// being encrypted when they were recorded.
+/// Every trace has the same number of samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceSet {
+    samples: usize,
+    plaintexts: Vec<[u8; 16]>,
+    traces: Vec<Vec<f64>>,
+}
+
+impl TraceSet {
+    /// An empty set of traces `samples` long.
+    pub fn new(samples: usize) -> Self {
+        Self {
+            samples,
+            plaintexts: Vec::new(),
+            traces: Vec::new(),
+        }
+    }
+
+    /// Panics if the trace does not have the same number of samples as the others.
+    pub fn push(&mut self, plaintext: [u8; 16], trace: Vec<f64>) {
+        assert_eq!(trace.len(), self.samples, "traces must be the same length");
+        self.plaintexts.push(plaintext);
+        self.traces.push(trace);
+    }
+
+    pub fn len(&self) -> usize {
+        self.traces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.traces.is_empty()
+    }
+
+    /// How many samples in each trace.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    pub fn plaintexts(&self) -> &[[u8; 16]] {
+        &self.plaintexts
+    }
+
+    pub fn traces(&self) -> &[Vec<f64>] {
+        &self.traces
+    }
+
+    /// Sample `i` of every trace.
+    pub fn column(&self, i: usize) -> Vec<f64> {
+        self.traces.iter().map(|trace| trace[i]).collect()
+    }
+
+    /// Serialize the trace set: the magic, the version, the number of traces
+    /// and of samples as u32s, then each plaintext followed by its samples,
+    /// all little-endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&(self.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.samples as u32).to_le_bytes());
+        for (plaintext, trace) in self.plaintexts.iter().zip(&self.traces) {
+            bytes.extend_from_slice(plaintext);
+            for sample in trace {
+                bytes.extend_from_slice(&sample.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    /// Deserialize a trace set written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TraceSetError> {
+        let rest = bytes.strip_prefix(MAGIC).ok_or(TraceSetError::BadMagic)?;
+        let (&version, rest) = rest.split_first().ok_or(TraceSetError::InvalidLength)?;
+        if version != VERSION {
+            return Err(TraceSetError::UnsupportedVersion(version));
+        }
+        if rest.len() < 8 {
+            return Err(TraceSetError::InvalidLength);
+        }
+        let u32_at = |i: usize| u32::from_le_bytes(rest[i..i + 4].try_into().unwrap()) as usize;
+        let (count, samples) = (u32_at(0), u32_at(4));
+        let rest = &rest[8..];
+
+        let record = samples
+            .checked_mul(8)
+            .and_then(|size| size.checked_add(16))
+            .ok_or(TraceSetError::InvalidLength)?;
+        let expected = count
+            .checked_mul(record)
+            .ok_or(TraceSetError::InvalidLength)?;
+        if rest.len() != expected {
+            return Err(TraceSetError::InvalidLength);
+        }
+        let mut set = TraceSet::new(samples);
+        for chunk in rest.chunks(record) {
+            let plaintext = chunk[..16].try_into().unwrap();
+            let trace = chunk[16..]
+                .chunks(8)
+                .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+                .collect();
+            set.push(plaintext, trace);
+        }
+        Ok(set)
+    }
+
+    /// Write the serialized trace set to a file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    /// Read a serialized trace set from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        TraceSet::from_bytes(&fs::read(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set() -> TraceSet {
+        let mut set = TraceSet::new(3);
+        set.push([1; 16], vec![0.5, -1.25, 3.0]);
+        set.push([2; 16], vec![4.0, 0.0, f64::MAX]);
+        set
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let set = set();
+        assert_eq!(set.column(0), vec![0.5, 4.0]);
+        assert_eq!(TraceSet::from_bytes(&set.to_bytes()), Ok(set.clone()));
+
+        let name = format!("crypto_test_trace_set_{}", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        set.save(&path).unwrap();
+        assert_eq!(TraceSet::load(&path).unwrap(), set);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_from_bytes_errors() {
+        let bytes = set().to_bytes();
+        assert_eq!(TraceSet::from_bytes(b"NGRM"), Err(TraceSetError::BadMagic));
+        let mut newer = bytes.clone();
+        newer[4] = 2;
+        assert_eq!(
+            TraceSet::from_bytes(&newer),
+            Err(TraceSetError::UnsupportedVersion(2))
+        );
+        assert_eq!(
+            TraceSet::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(TraceSetError::InvalidLength)
+        );
+        assert_eq!(
+            TraceSet::from_bytes(&bytes[..7]),
+            Err(TraceSetError::InvalidLength)
+        );
+
+        // sizes whose product doesn't fit, or wraps around to the length
+        let header = |count: u32, samples: u32| {
+            let mut header = bytes[..5].to_vec();
+            header.extend_from_slice(&count.to_le_bytes());
+            header.extend_from_slice(&samples.to_le_bytes());
+            header
+        };
+        assert_eq!(
+            TraceSet::from_bytes(&header(u32::MAX, u32::MAX)),
+            Err(TraceSetError::InvalidLength)
+        );
+        assert_eq!(
+            TraceSet::from_bytes(&header(u32::MAX, u32::MAX - 1)),
+            Err(TraceSetError::InvalidLength)
+        );
+        assert_eq!(
+            TraceSet::from_bytes(&header(1 << 31, (1 << 30) - 2)),
+            Err(TraceSetError::InvalidLength)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_push_wrong_length() {
+        set().push([0; 16], vec![1.0]);
+    }
+}
@@ -1,4 +1,4 @@
-use super::constants::RCON;
+use crate::math::gf256;
 use std::fmt::{self, Debug, Write};
 use std::ops::{Add, BitXor, Div, Index, Mul, Rem};
 use std::vec;
@@ -194,9 +194,12 @@ impl Word {
         }
     }
 
-    /// Return a new Word from the round constant
+    /// Return a new Word from the round constant,
+    /// which is x^(i-1) in the field.
+    /// Computed rather than looked up, so there are
+    /// round constants for as many rounds as wanted.
     pub fn rcon(i: u8) -> Word {
-        let rcon = RCON[(i - 1) as usize];
+        let rcon = gf256::pow(2, i as u32 - 1);
         Word::new(
             [Byte::new(rcon), Byte::new(0), Byte::new(0), Byte::new(0)],
             Endian::Big,
@@ -209,6 +212,16 @@ impl Word {
         self.bytes.to_hex()
     }
 
+    /// Returns the four bytes of the word as numbers.
+    pub fn to_numbers(&self) -> [u8; 4] {
+        [
+            self[0].get_number(),
+            self[1].get_number(),
+            self[2].get_number(),
+            self[3].get_number(),
+        ]
+    }
+
     /// Sets a byte at a certain position to a new value.
     pub fn set_byte(&mut self, position: usize, new_byte: Byte) {
         self.bytes.bytes[position] = new_byte
@@ -498,20 +511,9 @@ impl Mul for Byte {
     /// as the multiplication of the two bytes
     /// modulo M(x), which is 0x11B.
     /// See section 2.1.2 of the AES proposal.
+    /// The actual work is done by `math::gf256`.
     fn mul(self, rhs: Self) -> Self {
-        let mut mult = [0; 16];
-        for (i, a) in self.polynomial.iter().enumerate() {
-            for (j, b) in rhs.polynomial.iter().enumerate() {
-                if a * b == 1 {
-                    mult[i + j] ^= 1
-                }
-            }
-        }
-
-        let mx = [1, 1, 0, 1, 1, 0, 0, 0, 1];
-
-        let (_, remainder) = Self::divide(&mult, &mx);
-        Self::new(remainder as u8)
+        Self::new(gf256::mul(self.n, rhs.n))
     }
 }
 
@@ -577,6 +579,9 @@ mod test {
     #[test]
     fn test_rcon() {
         assert_eq!(Word::rcon(1).to_hex(), "01000000");
+        assert_eq!(Word::rcon(9).to_hex(), "1b000000");
+        assert_eq!(Word::rcon(10).to_hex(), "36000000");
+        assert_eq!(Word::rcon(11).to_hex(), "6c000000");
     }
 
     #[test]
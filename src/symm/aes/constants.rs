@@ -1,5 +1,3 @@
-pub static RCON: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
-
 /// The AES substitution box.
 pub static SBOX: [[u8; 16]; 16] = [
     [
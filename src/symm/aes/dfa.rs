@@ -0,0 +1,187 @@
+//! Differential fault analysis of AES-128, as in Piret and Quisquater's
+//! "A Differential Fault Attack Technique against SPN Structures".
+//!
+//! A byte of the state gets a fault right before the MixColumns of
+//! round 9. MixColumns spreads it over one column, and the last round
+//! just moves that column's bytes around, so the correct and faulty
+//! ciphertexts differ in four bytes whose differences, undone through
+//! the last SubBytes, must be a multiple of the MixColumns coefficients.
+//! Each pair leaves about 2^10 guesses for those four key bytes,
+//! and two or three pairs on the same column usually leave one.
+use super::{inverse_sbox, invert_key_schedule};
+use crate::math::gf256;
+use std::collections::HashSet;
+
+/// The MixColumns matrix: a difference `f` in row `r`
+/// becomes `MIX[i][r] * f` in row `i`.
+const MIX: [[u8; 4]; 4] = [[2, 3, 1, 1], [1, 2, 3, 1], [1, 1, 2, 3], [3, 1, 1, 2]];
+
+/// Where the bytes of column `column` of the state before the last round
+/// end up in the ciphertext, after ShiftRows moved them.
+pub fn ciphertext_positions(column: usize) -> [usize; 4] {
+    let mut positions = [0; 4];
+    for (row, position) in positions.iter_mut().enumerate() {
+        *position = 4 * ((column + 4 - row) % 4) + row;
+    }
+    positions
+}
+
+/// The column of the state which got the fault, if the ciphertexts
+/// differ exactly where a fault before the last MixColumns would leave them.
+pub fn faulted_column(correct: &[u8; 16], faulty: &[u8; 16]) -> Option<usize> {
+    let differ: Vec<usize> = (0..16).filter(|&i| correct[i] != faulty[i]).collect();
+    (0..4).find(|&column| {
+        let mut positions = ciphertext_positions(column).to_vec();
+        positions.sort_unstable();
+        positions == differ
+    })
+}
+
+/// Every guess of the four last round key bytes of the faulted column,
+/// in the order of `ciphertext_positions`, that explains the pair.
+/// None if the pair does not look like a fault before the last MixColumns.
+pub fn candidates(correct: &[u8; 16], faulty: &[u8; 16]) -> Option<HashSet<[u8; 4]>> {
+    let column = faulted_column(correct, faulty)?;
+    let positions = ciphertext_positions(column);
+
+    // for each row, the key bytes giving each difference before SubBytes
+    let mut by_difference = vec![vec![Vec::new(); 256]; 4];
+    for (row, &position) in positions.iter().enumerate() {
+        for k in 0..=255u8 {
+            let difference =
+                inverse_sbox(correct[position] ^ k) ^ inverse_sbox(faulty[position] ^ k);
+            by_difference[row][difference as usize].push(k);
+        }
+    }
+
+    let mut candidates = HashSet::new();
+    for faulted_row in 0..4 {
+        for f in 1..=255 {
+            let keys: Vec<&Vec<u8>> = (0..4)
+                .map(|row| &by_difference[row][gf256::mul(MIX[row][faulted_row], f) as usize])
+                .collect();
+            for &k0 in keys[0] {
+                for &k1 in keys[1] {
+                    for &k2 in keys[2] {
+                        for &k3 in keys[3] {
+                            candidates.insert([k0, k1, k2, k3]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Some(candidates)
+}
+
+/// Recovers the last round key of AES-128 from pairs of correct and faulty
+/// ciphertexts of the same plaintexts, with faults before the last MixColumns.
+/// Pairs which don't look like such a fault are skipped.
+/// None unless the pairs narrow every column down to one guess.
+pub fn attack(pairs: &[([u8; 16], [u8; 16])]) -> Option<[u8; 16]> {
+    let mut columns: Vec<Option<HashSet<[u8; 4]>>> = vec![None; 4];
+    for (correct, faulty) in pairs {
+        let column = match faulted_column(correct, faulty) {
+            Some(column) => column,
+            None => continue,
+        };
+        let found = candidates(correct, faulty)?;
+        columns[column] = Some(match columns[column].take() {
+            Some(previous) => previous.intersection(&found).copied().collect(),
+            None => found,
+        });
+    }
+
+    let mut round_key = [0; 16];
+    for (column, candidates) in columns.iter().enumerate() {
+        let candidates = candidates.as_ref()?;
+        if candidates.len() != 1 {
+            return None;
+        }
+        let guess = candidates.iter().next()?;
+        for (&position, &k) in ciphertext_positions(column).iter().zip(guess) {
+            round_key[position] = k;
+        }
+    }
+    Some(round_key)
+}
+
+/// Like `attack`, but runs the key schedule backwards
+/// from the last round key to get the AES-128 key.
+pub fn recover_key(pairs: &[([u8; 16], [u8; 16])]) -> Option<Vec<u8>> {
+    let round_key = attack(pairs)?;
+    invert_key_schedule(&round_key, 40)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Cipher;
+    use super::*;
+    use crate::random;
+
+    const KEY: &[u8; 16] = b"YELLOW SUBMARINE";
+
+    /// Three faulty encryptions for each column, at random rows, with random masks.
+    fn pairs() -> Vec<([u8; 16], [u8; 16])> {
+        let mut cipher = Cipher::new(KEY);
+        random::with_rng(3, || {
+            (0..12)
+                .map(|i| {
+                    let position = 4 * (i % 4) + random::in_range(0, 4);
+                    let mask = random::in_range(1, 255) as u8;
+                    let plaintext = random::get_random(16);
+                    cipher.set_state(&plaintext);
+                    let correct = cipher.encrypt();
+                    cipher.set_state(&plaintext);
+                    (correct, cipher.encrypt_with_fault(position, mask))
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn test_fault_spreads_over_one_column() {
+        let mut cipher = Cipher::new(KEY);
+        cipher.set_state(&[0; 16]);
+        let correct = cipher.encrypt();
+        for position in 0..16 {
+            cipher.set_state(&[0; 16]);
+            let faulty = cipher.encrypt_with_fault(position, 0x80);
+            assert_eq!(faulted_column(&correct, &faulty), Some(position / 4));
+        }
+        cipher.set_state(&[0; 16]);
+        assert_eq!(cipher.encrypt(), correct);
+    }
+
+    #[test]
+    fn test_candidates_contain_key() {
+        let last = Cipher::new(KEY).round_keys()[10];
+        for (correct, faulty) in pairs() {
+            let column = faulted_column(&correct, &faulty).unwrap();
+            let positions = ciphertext_positions(column);
+            let expected = [
+                last[positions[0]],
+                last[positions[1]],
+                last[positions[2]],
+                last[positions[3]],
+            ];
+            assert!(candidates(&correct, &faulty).unwrap().contains(&expected));
+        }
+    }
+
+    #[test]
+    fn test_recover_key() {
+        let pairs = pairs();
+        assert_eq!(attack(&pairs), Some(Cipher::new(KEY).round_keys()[10]));
+        assert_eq!(recover_key(&pairs), Some(KEY.to_vec()));
+        // one pair per column is not enough
+        assert_eq!(attack(&pairs[..4]), None);
+    }
+
+    #[test]
+    fn test_not_a_fault() {
+        assert_eq!(faulted_column(&[0; 16], &[0; 16]), None);
+        assert_eq!(faulted_column(&[0; 16], &[1; 16]), None);
+        assert_eq!(candidates(&[0; 16], &[1; 16]), None);
+    }
+}
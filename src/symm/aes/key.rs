@@ -1,5 +1,6 @@
 use super::bytes::{Bytes, Endian, Word, NB};
 use super::constants::SBOX;
+use crate::math::gf256;
 use std::ops::Index;
 use std::vec;
 
@@ -23,7 +24,7 @@ impl Key {
         // FIPS-197 seciont 5.2
         // Nb is the number of columns in a block, always 4, and Nr the number of rounds.
         let mut i = 0 as usize;
-        let mut words = Vec::with_capacity((NB * (rounds + 1)) as usize);
+        let mut words = Vec::with_capacity(NB as usize * (rounds as usize + 1));
         let key_length = key.len() as u8 / 4;
         while i < key_length as usize {
             let bytes = [key[4 * i], key[4 * i + 1], key[4 * i + 2], key[4 * i + 3]];
@@ -33,11 +34,11 @@ impl Key {
         }
         assert!(i == key_length as usize);
 
-        while i < (NB * (rounds + 1)) as usize {
+        while i < NB as usize * (rounds as usize + 1) {
             let mut temp = words[i - 1].clone();
-            if i as u8 % key_length == 0 {
-                temp = temp.rotword().subword(SBOX) ^ Word::rcon(i as u8 / key_length);
-            } else if key_length == 8 && i as u8 % key_length == 4 {
+            if i.is_multiple_of(key_length as usize) {
+                temp = temp.rotword().subword(SBOX) ^ Word::rcon((i / key_length as usize) as u8);
+            } else if key_length == 8 && i % key_length as usize == 4 {
                 temp = temp.subword(SBOX);
             }
             // i could use mem::swap but heck why not clone
@@ -46,7 +47,7 @@ impl Key {
             words.push(new_word);
             i += 1;
         }
-        assert!(words.len() == (NB * (rounds + 1)) as usize);
+        assert!(words.len() == NB as usize * (rounds as usize + 1));
         words
     }
 
@@ -56,6 +57,48 @@ impl Key {
     }
 }
 
+/// Runs the key schedule backwards: from Nk consecutive words of the
+/// expanded key starting at word `first_word`, gets the cipher key back.
+/// `words` are the 16, 24 or 32 bytes of those Nk words, so for AES-128
+/// any round key will do, `first_word` being four times its round.
+/// Works for schedules of any number of rounds.
+/// None if the words are not 4, 6 or 8.
+pub fn invert_key_schedule(words: &[u8], first_word: usize) -> Option<Vec<u8>> {
+    let key_length = words.len() / 4;
+    if !words.len().is_multiple_of(4) || ![4, 6, 8].contains(&key_length) {
+        return None;
+    }
+
+    let mut schedule = vec![[0; 4]; first_word + key_length];
+    for (i, word) in words.chunks(4).enumerate() {
+        schedule[first_word + i].copy_from_slice(word);
+    }
+    // w[i] = w[i - Nk] ^ temp(w[i - 1]), so w[i - Nk] = w[i] ^ temp(w[i - 1]),
+    // and going down, w[i - 1] is always known by then
+    for i in (key_length..first_word + key_length).rev() {
+        let temp = schedule_temp(schedule[i - 1], i, key_length);
+        for j in 0..4 {
+            schedule[i - key_length][j] = schedule[i][j] ^ temp[j];
+        }
+    }
+    Some(schedule[..key_length].concat())
+}
+
+/// What is XORed with w[i - Nk] to get w[i] in the key expansion,
+/// on plain bytes.
+fn schedule_temp(previous: [u8; 4], i: usize, key_length: usize) -> [u8; 4] {
+    let sub = |b: u8| SBOX[(b >> 4) as usize][(b & 0x0f) as usize];
+    if i.is_multiple_of(key_length) {
+        let [a, b, c, d] = previous;
+        let rcon = gf256::pow(2, (i / key_length - 1) as u32);
+        [sub(b) ^ rcon, sub(c), sub(d), sub(a)]
+    } else if key_length == 8 && i % key_length == 4 {
+        previous.map(sub)
+    } else {
+        previous
+    }
+}
+
 impl Index<usize> for Key {
     type Output = Word;
 
@@ -75,6 +118,7 @@ impl IntoIterator for Key {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::encoding::hex;
 
     #[test]
     fn test_key_expansion_128_bits() {
@@ -101,6 +145,33 @@ mod tests {
         assert_eq!(key.words[51].to_hex(), "01002202");
     }
 
+    #[test]
+    fn test_invert_key_schedule_from_last_round_key() {
+        // FIPS-197 appendix A.1, words 40 to 43
+        let last = hex::from_string("d014f9a8c9ee2589e13f0cc8b6630ca6").unwrap();
+        let key = invert_key_schedule(&last, 40).unwrap();
+        assert_eq!(hex::to_string(&key), "2B7E151628AED2A6ABF7158809CF4F3C");
+    }
+
+    #[test]
+    fn test_invert_key_schedule_all_lengths() {
+        for &key_length in &[16, 24, 32] {
+            let key: Vec<u8> = (0..key_length as u8).map(|b| b.wrapping_mul(37)).collect();
+            let rounds = key_length as u8 / 4 + 6;
+            let words: Vec<u8> = Key::new(Bytes::new(&key, Endian::Big), rounds)
+                .unwrap()
+                .into_iter()
+                .flat_map(|w| w.to_numbers().to_vec())
+                .collect();
+            let nk = key_length / 4;
+            for first_word in 0..=words.len() / 4 - nk {
+                let window = &words[first_word * 4..(first_word + nk) * 4];
+                assert_eq!(invert_key_schedule(window, first_word), Some(key.clone()));
+            }
+        }
+        assert_eq!(invert_key_schedule(&[0; 20], 0), None);
+    }
+
     #[test]
     fn test_key_expansion_256_bits() {
         let key = "
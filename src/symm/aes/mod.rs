@@ -1,10 +1,25 @@
 mod bytes;
 mod constants;
+pub mod dfa;
 mod key;
+pub mod square;
+mod trace;
 
 use super::modes;
 use bytes::{Block, Byte, Bytes, Endian, Word, NB};
+pub use key::invert_key_schedule;
 use key::Key;
+pub use trace::{diff, Divergence, Entry, ParseTraceError, ParseTraceErrorKind, Step, Trace};
+
+/// The AES S-box, what SubBytes does to a single byte.
+pub fn sbox(byte: u8) -> u8 {
+    constants::SBOX[(byte >> 4) as usize][(byte & 0x0f) as usize]
+}
+
+/// The inverse S-box, what InvSubBytes does to a single byte.
+pub fn inverse_sbox(byte: u8) -> u8 {
+    constants::INVERSE_SBOX[(byte >> 4) as usize][(byte & 0x0f) as usize]
+}
 
 /// A low-level AES Cipher.
 /// It provides the basic primitives of the AES algorithm.
@@ -17,8 +32,11 @@ use key::Key;
 /// - A call to set_state is made before trying to encrypt or decrypt
 pub struct Cipher {
     nr: u8,
+    full_last_round: bool,
     state: Block,
     key: Key,
+    trace: Option<Trace>,
+    fault: Option<(usize, u8)>,
 }
 
 impl Cipher {
@@ -29,14 +47,54 @@ impl Cipher {
             32 => 14,
             other => panic!("impossible key length: {}", other),
         };
+        Self::new_with_rounds(key, nr, false)
+    }
+
+    /// An AES with `rounds` rounds instead of the standard ones,
+    /// for cryptanalysis. The key schedule is just run for longer
+    /// or shorter. If `full_last_round` is set the last round
+    /// does MixColumns too, like every other round.
+    pub fn new_with_rounds(key: &[u8], rounds: u8, full_last_round: bool) -> Self {
+        if rounds == 0 {
+            panic!("impossible number of rounds: 0");
+        }
 
         let key_bytes = Bytes::new(key, Endian::Big);
-        let key = Key::new(key_bytes, nr).unwrap_or_else(|| panic!("could not generate key"));
+        let key = Key::new(key_bytes, rounds).unwrap_or_else(|| panic!("could not generate key"));
         Self {
-            nr,
+            nr: rounds,
+            full_last_round,
             state: Block::new([Word::zero(), Word::zero(), Word::zero(), Word::zero()]),
             key,
+            trace: None,
+            fault: None,
+        }
+    }
+
+    /// From now on, every encrypt or decrypt records what it
+    /// does to the state, to be picked up with `take_trace`.
+    pub fn start_tracing(&mut self) {
+        self.trace = Some(Trace::new());
+    }
+
+    /// The trace of the last encrypt or decrypt.
+    /// Tracing stops until `start_tracing` is called again.
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        self.trace.take()
+    }
+
+    /// The round keys, from the cipher key XORed first
+    /// to the one of the last round.
+    pub fn round_keys(&self) -> Vec<[u8; 16]> {
+        (0..=self.nr).map(|round| self.round_key(round)).collect()
+    }
+
+    fn round_key(&self, round: u8) -> [u8; 16] {
+        let mut round_key = [0; 16];
+        for (i, column) in round_key.chunks_mut(4).enumerate() {
+            column.copy_from_slice(&self.key[round as usize * NB as usize + i].to_numbers());
         }
+        round_key
     }
 
     pub fn set_state(&mut self, state: &[u8]) {
@@ -50,39 +108,147 @@ impl Cipher {
     }
 
     pub fn encrypt(&mut self) -> [u8; 16] {
+        self.begin_trace();
+        self.record(0, Step::Input, false);
+        self.record_key(0, 0, false);
         self.add_round_key(0);
 
         for round in 1..self.nr {
+            self.record(round, Step::Start, false);
             self.substitute_bytes();
+            self.record(round, Step::SubBytes, false);
             self.shift_rows();
+            self.record(round, Step::ShiftRows, false);
+            if round == self.last_mix_columns() {
+                self.inject_fault();
+            }
             self.mix_columns();
+            self.record(round, Step::MixColumns, false);
+            self.record_key(round, round, false);
             self.add_round_key(round);
         }
 
+        self.record(self.nr, Step::Start, false);
         self.substitute_bytes();
+        self.record(self.nr, Step::SubBytes, false);
         self.shift_rows();
+        self.record(self.nr, Step::ShiftRows, false);
+        if self.full_last_round {
+            self.inject_fault();
+            self.mix_columns();
+            self.record(self.nr, Step::MixColumns, false);
+        }
+        self.record_key(self.nr, self.nr, false);
         self.add_round_key(self.nr);
+        self.record(self.nr, Step::Output, false);
 
         self.state.flatten_into_u8()
     }
 
+    /// Encrypts like `encrypt`, but XORs `mask` into byte `position` of
+    /// the state right before the last MixColumns, which is in round 9
+    /// for AES-128, or in the last round with `full_last_round`.
+    /// Like a glitch would on a real device.
+    /// Panics if `position` is not below 16, or if the cipher does no
+    /// MixColumns at all: a single round without `full_last_round`.
+    pub fn encrypt_with_fault(&mut self, position: usize, mask: u8) -> [u8; 16] {
+        assert!(
+            position < 16,
+            "fault position {} is not in the state",
+            position
+        );
+        assert!(
+            self.last_mix_columns() > 0,
+            "no MixColumns to put the fault before"
+        );
+        self.fault = Some((position, mask));
+        let ciphertext = self.encrypt();
+        self.fault = None;
+        ciphertext
+    }
+
     pub fn decrypt(&mut self) -> [u8; 16] {
+        self.begin_trace();
+        self.record(0, Step::Input, true);
+        self.record_key(0, self.nr, true);
         self.add_round_key(self.nr);
+        if self.full_last_round {
+            self.inverse_mix_columns();
+            self.record(0, Step::MixColumns, true);
+        }
 
         for round in 1..self.nr {
+            self.record(round, Step::Start, true);
             self.inverse_shift_rows();
+            self.record(round, Step::ShiftRows, true);
             self.inverse_substitute_bytes();
+            self.record(round, Step::SubBytes, true);
+            self.record_key(round, self.nr - round, true);
             self.add_round_key(self.nr - round);
+            self.record(round, Step::AddRoundKey, true);
             self.inverse_mix_columns();
         }
 
+        self.record(self.nr, Step::Start, true);
         self.inverse_shift_rows();
+        self.record(self.nr, Step::ShiftRows, true);
         self.inverse_substitute_bytes();
+        self.record(self.nr, Step::SubBytes, true);
+        self.record_key(self.nr, 0, true);
         self.add_round_key(0);
+        self.record(self.nr, Step::Output, true);
 
         self.state.flatten_into_u8()
     }
 
+    /// The round doing the last MixColumns, 0 if none does.
+    fn last_mix_columns(&self) -> u8 {
+        if self.full_last_round {
+            self.nr
+        } else {
+            self.nr - 1
+        }
+    }
+
+    fn inject_fault(&mut self) {
+        if let Some((position, mask)) = self.fault {
+            let mut state = self.state.flatten_into_u8();
+            state[position] ^= mask;
+            self.set_state(&state);
+        }
+    }
+
+    fn begin_trace(&mut self) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.entries.clear();
+        }
+    }
+
+    fn record(&mut self, round: u8, step: Step, inverse: bool) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.entries.push(Entry {
+                round,
+                step,
+                inverse,
+                state: self.state.flatten_into_u8(),
+            });
+        }
+    }
+
+    fn record_key(&mut self, round: u8, key_round: u8, inverse: bool) {
+        if self.trace.is_some() {
+            let state = self.round_key(key_round);
+            if let Some(trace) = self.trace.as_mut() {
+                trace.entries.push(Entry {
+                    round,
+                    step: Step::RoundKey,
+                    inverse,
+                    state,
+                });
+            }
+        }
+    }
+
     // Known as "SubBytes()" in the AES specification.
     fn substitute_bytes(&mut self) {
         let new_columns: Vec<Word> = self
@@ -223,7 +389,7 @@ impl modes::BlockCipher for Cipher {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::encoding::hex;
+    use crate::encoding::hex::{self, Case};
 
     fn cipher() -> Cipher {
         let cipher_key = [
@@ -233,6 +399,15 @@ mod tests {
         Cipher::new(&cipher_key)
     }
 
+    #[test]
+    fn test_sbox() {
+        assert_eq!(sbox(0x00), 0x63);
+        assert_eq!(sbox(0x53), 0xed);
+        for b in 0..=255 {
+            assert_eq!(inverse_sbox(sbox(b)), b);
+        }
+    }
+
     #[test]
     fn test_encrypt_simple() {
         let key = hex::from_string(&"000102030405060708090a0b0c0d0e0f").unwrap();
@@ -240,11 +415,175 @@ mod tests {
         let cipher = &mut Cipher::new(&key);
         cipher.set_state(&plain);
 
-        let result = hex::to_string(&cipher.encrypt()).to_ascii_lowercase();
+        let result = hex::encode(&cipher.encrypt(), Case::Lower);
 
         assert_eq!(result, "69c4e0d86a7b0430d8cdb78070b4c55a")
     }
 
+    #[test]
+    fn test_new_with_rounds() {
+        let key = hex::from_string("000102030405060708090a0b0c0d0e0f").unwrap();
+        let plain = hex::from_string("00112233445566778899aabbccddeeff").unwrap();
+
+        let cipher = &mut Cipher::new_with_rounds(&key, 10, false);
+        cipher.set_state(&plain);
+        let result = hex::encode(&cipher.encrypt(), Case::Lower);
+        assert_eq!(result, "69c4e0d86a7b0430d8cdb78070b4c55a");
+
+        for &(rounds, full) in &[(1, false), (4, true), (4, false), (20, true)] {
+            let cipher = &mut Cipher::new_with_rounds(&key, rounds, full);
+            assert_eq!(cipher.round_keys().len(), rounds as usize + 1);
+            cipher.set_state(&plain);
+            let encrypted = cipher.encrypt();
+            assert_ne!(encrypted.to_vec(), plain);
+            cipher.set_state(&encrypted);
+            assert_eq!(cipher.decrypt().to_vec(), plain);
+        }
+    }
+
+    #[test]
+    fn test_fault_before_last_mix_columns() {
+        let key = [0; 16];
+        // a full last round ends with MixColumns, so
+        // the fault stays in its column, unshifted
+        for &rounds in &[1, 10] {
+            let cipher = &mut Cipher::new_with_rounds(&key, rounds, true);
+            cipher.set_state(&[0; 16]);
+            let correct = cipher.encrypt();
+            for position in 0..16 {
+                cipher.set_state(&[0; 16]);
+                let faulty = cipher.encrypt_with_fault(position, 1);
+                let differ: Vec<usize> = (0..16).filter(|&i| correct[i] != faulty[i]).collect();
+                let column = 4 * (position / 4);
+                assert_eq!(differ, (column..column + 4).collect::<Vec<_>>());
+            }
+        }
+        // with two rounds, the last MixColumns is in the first
+        let cipher = &mut Cipher::new_with_rounds(&key, 2, false);
+        cipher.set_state(&[0; 16]);
+        let correct = cipher.encrypt();
+        cipher.set_state(&[0; 16]);
+        let faulty = cipher.encrypt_with_fault(0, 1);
+        assert_eq!(dfa::faulted_column(&correct, &faulty), Some(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "fault position 16")]
+    fn test_fault_position_out_of_state() {
+        let cipher = &mut Cipher::new(&[0; 16]);
+        cipher.set_state(&[0; 16]);
+        cipher.encrypt_with_fault(16, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "no MixColumns")]
+    fn test_fault_without_mix_columns() {
+        let cipher = &mut Cipher::new_with_rounds(&[0; 16], 1, false);
+        cipher.set_state(&[0; 16]);
+        cipher.encrypt_with_fault(0, 1);
+    }
+
+    #[test]
+    fn test_trace_encrypt() {
+        let key = hex::from_string("000102030405060708090a0b0c0d0e0f").unwrap();
+        let plain = hex::from_string("00112233445566778899aabbccddeeff").unwrap();
+        let cipher = &mut Cipher::new(&key);
+        cipher.set_state(&plain);
+        cipher.encrypt();
+        assert_eq!(cipher.take_trace(), None);
+
+        cipher.start_tracing();
+        cipher.set_state(&plain);
+        cipher.encrypt();
+        let trace = cipher.take_trace().unwrap().to_string();
+        let lines: Vec<&str> = trace.lines().collect();
+        // 2 for round 0, 5 per round, and a shorter last one
+        assert_eq!(lines.len(), 2 + 9 * 5 + 5);
+        // FIPS-197 appendix C.1
+        assert_eq!(
+            &lines[..7],
+            &[
+                "round[ 0].input    00112233445566778899aabbccddeeff",
+                "round[ 0].k_sch    000102030405060708090a0b0c0d0e0f",
+                "round[ 1].start    00102030405060708090a0b0c0d0e0f0",
+                "round[ 1].s_box    63cab7040953d051cd60e0e7ba70e18c",
+                "round[ 1].s_row    6353e08c0960e104cd70b751bacad0e7",
+                "round[ 1].m_col    5f72641557f5bc92f7be3b291db9f91a",
+                "round[ 1].k_sch    d6aa74fdd2af72fadaa678f1d6ab76fe",
+            ]
+        );
+        assert_eq!(
+            lines.last(),
+            Some(&"round[10].output   69c4e0d86a7b0430d8cdb78070b4c55a")
+        );
+    }
+
+    #[test]
+    fn test_trace_decrypt() {
+        let key = hex::from_string("000102030405060708090a0b0c0d0e0f").unwrap();
+        let cipher_text = hex::from_string("69c4e0d86a7b0430d8cdb78070b4c55a").unwrap();
+        let cipher = &mut Cipher::new(&key);
+        cipher.start_tracing();
+        cipher.set_state(&cipher_text);
+        cipher.decrypt();
+        let trace = cipher.take_trace().unwrap();
+        let lines: Vec<String> = trace.entries.iter().map(|e| e.to_string()).collect();
+        // FIPS-197 appendix C.1, inverse cipher
+        assert_eq!(
+            &lines[..7],
+            &[
+                "round[ 0].iinput   69c4e0d86a7b0430d8cdb78070b4c55a",
+                "round[ 0].ik_sch   13111d7fe3944a17f307a78b4d2b30c5",
+                "round[ 1].istart   7ad5fda789ef4e272bca100b3d9ff59f",
+                "round[ 1].is_row   7a9f102789d5f50b2beffd9f3dca4ea7",
+                "round[ 1].is_box   bd6e7c3df2b5779e0b61216e8b10b689",
+                "round[ 1].ik_sch   549932d1f08557681093ed9cbe2c974e",
+                "round[ 1].ik_add   e9f74eec023020f61bf2ccf2353c21c7",
+            ]
+        );
+        assert_eq!(
+            lines.last().map(String::as_str),
+            Some("round[10].ioutput  00112233445566778899aabbccddeeff")
+        );
+    }
+
+    #[test]
+    fn test_trace_diff_finds_round() {
+        let key = hex::from_string("000102030405060708090a0b0c0d0e0f").unwrap();
+        let plain = [0; 16];
+        let trace = |rounds| {
+            let cipher = &mut Cipher::new_with_rounds(&key, rounds, false);
+            cipher.start_tracing();
+            cipher.set_state(&plain);
+            cipher.encrypt();
+            cipher.take_trace().unwrap()
+        };
+        // the shorter cipher does its last round, without MixColumns, in round 4
+        let divergence = diff(&trace(10), &trace(4)).unwrap();
+        assert_eq!(divergence.round(), 4);
+        assert_eq!(divergence.expected.unwrap().step, Step::MixColumns);
+        assert_eq!(divergence.got.unwrap().step, Step::RoundKey);
+    }
+
+    #[test]
+    fn test_round_keys() {
+        let round_keys = cipher().round_keys();
+        assert_eq!(round_keys.len(), 11);
+        assert_eq!(
+            hex::encode(&round_keys[0], Case::Lower),
+            "2b7e151628aed2a6abf7158809cf4f3c"
+        );
+        // FIPS-197 appendix A.1
+        assert_eq!(
+            hex::encode(&round_keys[10], Case::Lower),
+            "d014f9a8c9ee2589e13f0cc8b6630ca6"
+        );
+        assert_eq!(
+            invert_key_schedule(&round_keys[7], 28).unwrap(),
+            round_keys[0]
+        );
+    }
+
     #[test]
     fn test_add_round_key() {
         let expected = Block::new([
@@ -0,0 +1,121 @@
+//! The square (integral) attack on 4-round AES.
+//!
+//! Encrypt a Λ-set: 256 plaintexts where one byte takes every value
+//! and the rest stay constant. After three rounds every byte of the
+//! state XORs to zero over the set. The fourth round is the last one,
+//! without MixColumns, so guessing a single byte of its round key is
+//! enough to undo it for that byte and check the sum.
+use super::{inverse_sbox, invert_key_schedule};
+
+/// How many Λ-sets to try before giving up on
+/// narrowing every byte down to a single guess.
+pub const MAX_SETS: usize = 16;
+
+/// The 256 plaintexts of a Λ-set: byte `active` takes every value,
+/// the others are the ones in `constant`.
+pub fn lambda_set(active: usize, constant: &[u8; 16]) -> Vec<[u8; 16]> {
+    (0..=255)
+        .map(|value| {
+            let mut plaintext = *constant;
+            plaintext[active] = value;
+            plaintext
+        })
+        .collect()
+}
+
+/// Recovers the last round key of a 4-round AES (the standard last
+/// round, without MixColumns) from an encryption oracle.
+/// None if some byte still has several candidates after `MAX_SETS`
+/// Λ-sets, or none at all, which means the oracle is not 4-round AES.
+pub fn attack<F>(mut encrypt: F) -> Option<[u8; 16]>
+where
+    F: FnMut(&[u8; 16]) -> [u8; 16],
+{
+    let mut candidates: Vec<Vec<u8>> = vec![(0..=255).collect(); 16];
+
+    for set in 0..MAX_SETS {
+        let constant = [set as u8; 16];
+        let ciphertexts: Vec<[u8; 16]> =
+            lambda_set(0, &constant).iter().map(&mut encrypt).collect();
+
+        for (position, guesses) in candidates.iter_mut().enumerate() {
+            guesses.retain(|&guess| {
+                let bytes = ciphertexts.iter().map(|c| c[position]);
+                balanced(bytes, guess)
+            });
+        }
+
+        if candidates.iter().any(|guesses| guesses.is_empty()) {
+            return None;
+        }
+        if candidates.iter().all(|guesses| guesses.len() == 1) {
+            let mut round_key = [0; 16];
+            for (byte, guesses) in round_key.iter_mut().zip(&candidates) {
+                *byte = guesses[0];
+            }
+            return Some(round_key);
+        }
+    }
+    None
+}
+
+/// Like `attack`, but runs the key schedule backwards
+/// from the last round key to get the cipher key.
+pub fn recover_key<F>(encrypt: F) -> Option<Vec<u8>>
+where
+    F: FnMut(&[u8; 16]) -> [u8; 16],
+{
+    let round_key = attack(encrypt)?;
+    invert_key_schedule(&round_key, 16)
+}
+
+/// Whether undoing the last round for one byte with the
+/// key byte `guess` makes the bytes XOR to zero.
+fn balanced(bytes: impl Iterator<Item = u8>, guess: u8) -> bool {
+    bytes
+        .map(|c| inverse_sbox(c ^ guess))
+        .fold(0, |sum, b| sum ^ b)
+        == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Cipher;
+    use super::*;
+
+    fn oracle(key: &[u8]) -> impl FnMut(&[u8; 16]) -> [u8; 16] {
+        let mut cipher = Cipher::new_with_rounds(key, 4, false);
+        move |plaintext| {
+            cipher.set_state(plaintext);
+            cipher.encrypt()
+        }
+    }
+
+    #[test]
+    fn test_lambda_set() {
+        let set = lambda_set(3, &[7; 16]);
+        assert_eq!(set.len(), 256);
+        for (value, plaintext) in set.iter().enumerate() {
+            assert_eq!(plaintext[3] as usize, value);
+            assert!(plaintext.iter().enumerate().all(|(i, &b)| i == 3 || b == 7));
+        }
+    }
+
+    #[test]
+    fn test_attack_recovers_last_round_key() {
+        let key = b"YELLOW SUBMARINE";
+        let expected = Cipher::new_with_rounds(key, 4, false).round_keys()[4];
+        assert_eq!(attack(oracle(key)), Some(expected));
+        assert_eq!(recover_key(oracle(key)), Some(key.to_vec()));
+    }
+
+    #[test]
+    fn test_attack_fails_on_more_rounds() {
+        let mut cipher = Cipher::new_with_rounds(b"YELLOW SUBMARINE", 5, false);
+        let oracle = |plaintext: &[u8; 16]| {
+            cipher.set_state(plaintext);
+            cipher.encrypt()
+        };
+        assert_eq!(attack(oracle), None);
+    }
+}
@@ -0,0 +1,338 @@
+use crate::encoding::hex::{self, Case};
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::{error, fmt};
+
+/// What had just been done to the state when an entry was recorded.
+/// `RoundKey` entries hold the round key instead of the state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Input,
+    Start,
+    SubBytes,
+    ShiftRows,
+    MixColumns,
+    RoundKey,
+    AddRoundKey,
+    Output,
+}
+
+impl Step {
+    const ALL: [Step; 8] = [
+        Step::Input,
+        Step::Start,
+        Step::SubBytes,
+        Step::ShiftRows,
+        Step::MixColumns,
+        Step::RoundKey,
+        Step::AddRoundKey,
+        Step::Output,
+    ];
+
+    /// The name FIPS-197 gives the step in its examples,
+    /// with an `i` in front for the inverse cipher.
+    pub fn label(self, inverse: bool) -> String {
+        let label = match self {
+            Step::Input => "input",
+            Step::Start => "start",
+            Step::SubBytes => "s_box",
+            Step::ShiftRows => "s_row",
+            Step::MixColumns => "m_col",
+            Step::RoundKey => "k_sch",
+            Step::AddRoundKey => "k_add",
+            Step::Output => "output",
+        };
+        if inverse {
+            format!("i{}", label)
+        } else {
+            label.to_string()
+        }
+    }
+
+    fn from_label(label: &str) -> Option<(Step, bool)> {
+        let find = |label: &str, inverse: bool| {
+            Step::ALL
+                .iter()
+                .find(|step| step.label(inverse) == label)
+                .map(|&step| (step, inverse))
+        };
+        find(label, false).or_else(|| find(label, true))
+    }
+}
+
+/// The state, or round key, at some step of some round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub round: u8,
+    pub step: Step,
+    pub inverse: bool,
+    pub state: [u8; 16],
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "round[{:2}].{:<9}{}",
+            self.round,
+            self.step.label(self.inverse),
+            hex::encode(&self.state, Case::Lower)
+        )
+    }
+}
+
+/// Everything a `Cipher` did to a block, step by step.
+/// Prints, and parses, like the examples in
+/// appendices B and C of FIPS-197, one entry per line:
+///
+/// ```text
+/// round[ 0].input    00112233445566778899aabbccddeeff
+/// round[ 0].k_sch    000102030405060708090a0b0c0d0e0f
+/// round[ 1].start    00102030405060708090a0b0c0d0e0f0
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    pub entries: Vec<Entry>,
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The state after the last step.
+    pub fn output(&self) -> Option<[u8; 16]> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.step != Step::RoundKey)
+            .map(|entry| entry.state)
+    }
+
+    /// The first entry where `other` does something else than this trace,
+    /// or None if they are the same.
+    pub fn first_divergence(&self, other: &Trace) -> Option<Divergence> {
+        diff(self, other)
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+/// Why a trace could not be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseTraceErrorKind {
+    /// Not something like `round[ 1].s_box 63cab704...`.
+    Malformed,
+    /// A step FIPS-197 does not have.
+    UnknownStep(String),
+    /// The state is not 16 bytes of hex.
+    InvalidState,
+}
+
+/// An error parsing a trace, with the line it happened on, from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseTraceError {
+    pub line: usize,
+    pub kind: ParseTraceErrorKind,
+}
+
+impl fmt::Display for ParseTraceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ParseTraceErrorKind::Malformed => write!(f, "malformed entry on line {}", self.line),
+            ParseTraceErrorKind::UnknownStep(step) => {
+                write!(f, "unknown step {:?} on line {}", step, self.line)
+            }
+            ParseTraceErrorKind::InvalidState => {
+                write!(f, "state is not 16 bytes of hex on line {}", self.line)
+            }
+        }
+    }
+}
+
+impl error::Error for ParseTraceError {}
+
+impl FromStr for Entry {
+    type Err = ParseTraceErrorKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s
+            .trim()
+            .strip_prefix("round[")
+            .ok_or(ParseTraceErrorKind::Malformed)?;
+        let (round, rest) = rest
+            .split_once("].")
+            .ok_or(ParseTraceErrorKind::Malformed)?;
+        let round = round
+            .trim()
+            .parse()
+            .map_err(|_| ParseTraceErrorKind::Malformed)?;
+        let (label, state) = rest
+            .split_once(char::is_whitespace)
+            .ok_or(ParseTraceErrorKind::Malformed)?;
+        let (step, inverse) = Step::from_label(label)
+            .ok_or_else(|| ParseTraceErrorKind::UnknownStep(label.to_string()))?;
+        let state = hex::from_string(state.trim())
+            .ok()
+            .and_then(|state| <[u8; 16]>::try_from(state).ok())
+            .ok_or(ParseTraceErrorKind::InvalidState)?;
+        Ok(Entry {
+            round,
+            step,
+            inverse,
+            state,
+        })
+    }
+}
+
+impl FromStr for Trace {
+    type Err = ParseTraceError;
+
+    /// Blank lines are skipped, so are lines not starting with `round[`,
+    /// which lets FIPS-197's examples be pasted whole, headers and all.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut trace = Trace::new();
+        for (i, line) in s.lines().enumerate() {
+            if !line.trim_start().starts_with("round[") {
+                continue;
+            }
+            let entry = line
+                .parse()
+                .map_err(|kind| ParseTraceError { line: i + 1, kind })?;
+            trace.entries.push(entry);
+        }
+        Ok(trace)
+    }
+}
+
+/// Where two traces stop agreeing. Either entry is None
+/// when its trace ran out first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub expected: Option<Entry>,
+    pub got: Option<Entry>,
+}
+
+impl Divergence {
+    /// The first round that went wrong.
+    pub fn round(&self) -> u8 {
+        match (self.expected, self.got) {
+            (Some(expected), Some(got)) => expected.round.min(got.round),
+            (Some(entry), None) | (None, Some(entry)) => entry.round,
+            (None, None) => 0,
+        }
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let line = |entry: &Option<Entry>| match entry {
+            Some(entry) => entry.to_string(),
+            None => "nothing".to_string(),
+        };
+        writeln!(f, "first divergence in round {}", self.round())?;
+        writeln!(f, "expected: {}", line(&self.expected))?;
+        write!(f, "     got: {}", line(&self.got))
+    }
+}
+
+/// Compares two traces entry by entry, and returns
+/// the first one that is not the same in both.
+pub fn diff(expected: &Trace, got: &Trace) -> Option<Divergence> {
+    let len = expected.entries.len().max(got.entries.len());
+    (0..len)
+        .map(|i| Divergence {
+            expected: expected.entries.get(i).copied(),
+            got: got.entries.get(i).copied(),
+        })
+        .find(|divergence| divergence.expected != divergence.got)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+PLAINTEXT:          00112233445566778899aabbccddeeff
+round[ 0].input    00112233445566778899aabbccddeeff
+round[ 0].k_sch    000102030405060708090a0b0c0d0e0f
+
+round[ 1].start    00102030405060708090a0b0c0d0e0f0
+round[ 1].s_box    63cab7040953d051cd60e0e7ba70e18c
+";
+
+    #[test]
+    fn test_parse_and_display() {
+        let trace: Trace = EXAMPLE.parse().unwrap();
+        assert_eq!(trace.entries.len(), 4);
+        assert_eq!(trace.entries[1].step, Step::RoundKey);
+        assert_eq!(trace.entries[3].round, 1);
+        assert_eq!(trace.output().unwrap()[..2], [0x63, 0xca]);
+
+        let printed = trace.to_string();
+        assert_eq!(
+            printed.lines().nth(2),
+            Some("round[ 1].start    00102030405060708090a0b0c0d0e0f0")
+        );
+        assert_eq!(printed.parse::<Trace>().unwrap(), trace);
+    }
+
+    #[test]
+    fn test_parse_inverse() {
+        let entry: Entry = "round[10].ioutput  00112233445566778899aabbccddeeff"
+            .parse()
+            .unwrap();
+        assert_eq!(entry.round, 10);
+        assert_eq!(entry.step, Step::Output);
+        assert!(entry.inverse);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |s: &str| s.parse::<Trace>().unwrap_err();
+        assert_eq!(
+            error("round[ 1].s_box"),
+            ParseTraceError {
+                line: 1,
+                kind: ParseTraceErrorKind::Malformed
+            }
+        );
+        assert_eq!(
+            error("\nround[ 1].sbox 00").kind,
+            ParseTraceErrorKind::UnknownStep("sbox".to_string())
+        );
+        assert_eq!(
+            error("round[ 1].s_box 0011").kind,
+            ParseTraceErrorKind::InvalidState
+        );
+        assert_eq!(
+            error("round[x].s_box 00").kind,
+            ParseTraceErrorKind::Malformed
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let expected: Trace = EXAMPLE.parse().unwrap();
+        assert_eq!(diff(&expected, &expected), None);
+
+        let mut got = expected.clone();
+        got.entries[3].state[15] ^= 1;
+        let divergence = expected.first_divergence(&got).unwrap();
+        assert_eq!(divergence.round(), 1);
+        assert_eq!(divergence.expected, Some(expected.entries[3]));
+        assert_eq!(divergence.got, Some(got.entries[3]));
+
+        got.entries.truncate(2);
+        let divergence = diff(&expected, &got).unwrap();
+        assert_eq!(divergence.got, None);
+        assert!(divergence.to_string().ends_with("got: nothing"));
+    }
+}
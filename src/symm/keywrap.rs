@@ -0,0 +1,283 @@
+//! AES Key Wrap, RFC 3394, and AES Key Wrap with Padding, RFC 5649.
+//! For storing keys encrypted under another key, the KEK, with
+//! an integrity check so a wrong KEK or a tampered key is noticed.
+use super::aes;
+use crate::bytes::constant_time_eq;
+use std::{error, fmt};
+
+type Result<T> = std::result::Result<T, KeyWrapError>;
+
+/// The initial value of RFC 3394, checked on unwrap.
+pub const DEFAULT_IV: [u8; 8] = [0xa6; 8];
+/// The first half of the alternative initial value of RFC 5649,
+/// the second half is the length of the key.
+const AIV_PREFIX: [u8; 4] = [0xa6, 0x59, 0x59, 0xa6];
+
+/// Why a key could not be wrapped or unwrapped.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyWrapError {
+    /// The KEK must be 16, 24 or 32 bytes.
+    InvalidKekLength(usize),
+    /// Too short, or not a multiple of 8 bytes where it has to be.
+    InvalidLength(usize),
+    /// The integrity check failed: wrong KEK, or the wrapped key was modified.
+    IntegrityCheckFailed,
+}
+
+impl fmt::Display for KeyWrapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyWrapError::InvalidKekLength(len) => write!(f, "invalid KEK length {}", len),
+            KeyWrapError::InvalidLength(len) => write!(f, "invalid length {}", len),
+            KeyWrapError::IntegrityCheckFailed => write!(f, "integrity check failed"),
+        }
+    }
+}
+
+impl error::Error for KeyWrapError {}
+
+/// Wraps `key`, which must be at least 16 bytes and a multiple of 8.
+pub fn wrap(kek: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    let mut cipher = cipher(kek)?;
+    if key.len() < 16 || !key.len().is_multiple_of(8) {
+        return Err(KeyWrapError::InvalidLength(key.len()));
+    }
+    Ok(wrap_blocks(&mut cipher, DEFAULT_IV, key))
+}
+
+/// Unwraps what `wrap` wrapped, checking it was wrapped with this KEK.
+pub fn unwrap(kek: &[u8], wrapped: &[u8]) -> Result<Vec<u8>> {
+    let mut cipher = cipher(kek)?;
+    if wrapped.len() < 24 || !wrapped.len().is_multiple_of(8) {
+        return Err(KeyWrapError::InvalidLength(wrapped.len()));
+    }
+    let (iv, key) = unwrap_blocks(&mut cipher, wrapped);
+    if !constant_time_eq(&iv, &DEFAULT_IV) {
+        return Err(KeyWrapError::IntegrityCheckFailed);
+    }
+    Ok(key)
+}
+
+/// Wraps a key of any length but 0, padding it with zeros to a
+/// multiple of 8. Keys up to 8 bytes are a single AES block.
+pub fn wrap_with_padding(kek: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    let mut cipher = cipher(kek)?;
+    if key.is_empty() || key.len() > u32::MAX as usize {
+        return Err(KeyWrapError::InvalidLength(key.len()));
+    }
+    let mut iv = [0; 8];
+    iv[..4].copy_from_slice(&AIV_PREFIX);
+    iv[4..].copy_from_slice(&(key.len() as u32).to_be_bytes());
+    let mut padded = key.to_vec();
+    padded.resize(key.len().div_ceil(8) * 8, 0);
+
+    if padded.len() == 8 {
+        cipher.set_state(&[&iv[..], &padded].concat());
+        return Ok(cipher.encrypt().to_vec());
+    }
+    Ok(wrap_blocks(&mut cipher, iv, &padded))
+}
+
+/// Unwraps what `wrap_with_padding` wrapped, checking the KEK,
+/// the length and that the padding is all zeros.
+pub fn unwrap_with_padding(kek: &[u8], wrapped: &[u8]) -> Result<Vec<u8>> {
+    let mut cipher = cipher(kek)?;
+    if wrapped.len() < 16 || !wrapped.len().is_multiple_of(8) {
+        return Err(KeyWrapError::InvalidLength(wrapped.len()));
+    }
+    let (iv, padded) = if wrapped.len() == 16 {
+        cipher.set_state(wrapped);
+        let block = cipher.decrypt();
+        (block[..8].to_vec(), block[8..].to_vec())
+    } else {
+        unwrap_blocks(&mut cipher, wrapped)
+    };
+
+    // every check is done before returning, so a failure
+    // doesn't tell which one it was by how long it took
+    let length = u32::from_be_bytes([iv[4], iv[5], iv[6], iv[7]]) as usize;
+    let prefix_ok = constant_time_eq(&iv[..4], &AIV_PREFIX);
+    let length_ok = length <= padded.len() && length + 8 > padded.len();
+    let padding_ok = padded[length.min(padded.len())..]
+        .iter()
+        .fold(0, |acc, b| acc | b)
+        == 0;
+    if !(prefix_ok && length_ok && padding_ok) {
+        return Err(KeyWrapError::IntegrityCheckFailed);
+    }
+    Ok(padded[..length].to_vec())
+}
+
+fn cipher(kek: &[u8]) -> Result<aes::Cipher> {
+    match kek.len() {
+        16 | 24 | 32 => Ok(aes::Cipher::new(kek)),
+        other => Err(KeyWrapError::InvalidKekLength(other)),
+    }
+}
+
+/// The wrapping process W of RFC 3394, section 2.2.1,
+/// in its index based form.
+fn wrap_blocks(cipher: &mut aes::Cipher, iv: [u8; 8], key: &[u8]) -> Vec<u8> {
+    let mut a = iv;
+    let mut r: Vec<[u8; 8]> = key
+        .chunks(8)
+        .map(|chunk| {
+            let mut block = [0; 8];
+            block.copy_from_slice(chunk);
+            block
+        })
+        .collect();
+    let n = r.len() as u64;
+
+    for j in 0..6 {
+        for (i, block) in r.iter_mut().enumerate() {
+            cipher.set_state(&[&a[..], &block[..]].concat());
+            let b = cipher.encrypt();
+            let t = (n * j + i as u64 + 1).to_be_bytes();
+            for k in 0..8 {
+                a[k] = b[k] ^ t[k];
+            }
+            block.copy_from_slice(&b[8..]);
+        }
+    }
+
+    let mut wrapped = a.to_vec();
+    for block in &r {
+        wrapped.extend_from_slice(block);
+    }
+    wrapped
+}
+
+/// The unwrapping process W^-1 of RFC 3394, section 2.2.2,
+/// returning the initial value it got back and the key.
+fn unwrap_blocks(cipher: &mut aes::Cipher, wrapped: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut a = [0; 8];
+    a.copy_from_slice(&wrapped[..8]);
+    let mut r: Vec<Vec<u8>> = wrapped[8..].chunks(8).map(|c| c.to_vec()).collect();
+    let n = r.len() as u64;
+
+    for j in (0..6).rev() {
+        for (i, block) in r.iter_mut().enumerate().rev() {
+            let t = (n * j + i as u64 + 1).to_be_bytes();
+            for k in 0..8 {
+                a[k] ^= t[k];
+            }
+            cipher.set_state(&[&a[..], &block[..]].concat());
+            let b = cipher.decrypt();
+            a.copy_from_slice(&b[..8]);
+            block.copy_from_slice(&b[8..]);
+        }
+    }
+    (a.to_vec(), r.concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::hex;
+
+    fn h(s: &str) -> Vec<u8> {
+        hex::from_string(s).unwrap()
+    }
+
+    #[test]
+    fn test_rfc3394_vectors() {
+        // 4.1, 4.3 and 4.6
+        let vectors = [
+            (
+                "000102030405060708090A0B0C0D0E0F",
+                "00112233445566778899AABBCCDDEEFF",
+                "1FA68B0A8112B447AEF34BD8FB5A7B829D3E862371D2CFE5",
+            ),
+            (
+                "000102030405060708090A0B0C0D0E0F1011121314151617",
+                "00112233445566778899AABBCCDDEEFF",
+                "96778B25AE6CA435F92B5B97C050AED2468AB8A17AD84E5D",
+            ),
+            (
+                "000102030405060708090A0B0C0D0E0F101112131415161718191A1B1C1D1E1F",
+                "00112233445566778899AABBCCDDEEFF000102030405060708090A0B0C0D0E0F",
+                "28C9F404C4B810F4CBCCB35CFB87F8263F5786E2D80ED326CBC7F0E71A99F43BFB988B9B7A02DD21",
+            ),
+        ];
+        for (kek, key, wrapped) in vectors.iter() {
+            assert_eq!(wrap(&h(kek), &h(key)), Ok(h(wrapped)));
+            assert_eq!(unwrap(&h(kek), &h(wrapped)), Ok(h(key)));
+        }
+    }
+
+    #[test]
+    fn test_rfc5649_vectors() {
+        let kek = h("5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8");
+        let vectors = [
+            (
+                "c37b7e6492584340bed12207808941155068f738",
+                "138bdeaa9b8fa7fc61f97742e72248ee5ae6ae5360d1ae6a5f54f373fa543b6a",
+            ),
+            ("466f7250617369", "afbeb0f07dfbf5419200f2ccb50bb24f"),
+        ];
+        for (key, wrapped) in vectors.iter() {
+            assert_eq!(wrap_with_padding(&kek, &h(key)), Ok(h(wrapped)));
+            assert_eq!(unwrap_with_padding(&kek, &h(wrapped)), Ok(h(key)));
+        }
+    }
+
+    #[test]
+    fn test_integrity_check() {
+        let kek = [1; 16];
+        let key = [2; 24];
+        let mut wrapped = wrap(&kek, &key).unwrap();
+        assert_eq!(
+            unwrap(&[3; 16], &wrapped),
+            Err(KeyWrapError::IntegrityCheckFailed)
+        );
+        wrapped[20] ^= 1;
+        assert_eq!(
+            unwrap(&kek, &wrapped),
+            Err(KeyWrapError::IntegrityCheckFailed)
+        );
+
+        for len in 1..=33 {
+            let key = vec![7; len];
+            let mut wrapped = wrap_with_padding(&kek, &key).unwrap();
+            assert_eq!(unwrap_with_padding(&kek, &wrapped), Ok(key));
+            let last = wrapped.len() - 1;
+            wrapped[last] ^= 1;
+            assert_eq!(
+                unwrap_with_padding(&kek, &wrapped),
+                Err(KeyWrapError::IntegrityCheckFailed)
+            );
+        }
+        // a key wrapped without padding doesn't unwrap as if it had some
+        let wrapped = wrap(&kek, &key).unwrap();
+        assert_eq!(
+            unwrap_with_padding(&kek, &wrapped),
+            Err(KeyWrapError::IntegrityCheckFailed)
+        );
+    }
+
+    #[test]
+    fn test_invalid_lengths() {
+        assert_eq!(
+            wrap(&[0; 15], &[0; 16]),
+            Err(KeyWrapError::InvalidKekLength(15))
+        );
+        assert_eq!(wrap(&[0; 16], &[0; 8]), Err(KeyWrapError::InvalidLength(8)));
+        assert_eq!(
+            wrap(&[0; 16], &[0; 20]),
+            Err(KeyWrapError::InvalidLength(20))
+        );
+        assert_eq!(
+            unwrap(&[0; 16], &[0; 16]),
+            Err(KeyWrapError::InvalidLength(16))
+        );
+        assert_eq!(
+            wrap_with_padding(&[0; 16], &[]),
+            Err(KeyWrapError::InvalidLength(0))
+        );
+        assert_eq!(
+            unwrap_with_padding(&[0; 16], &[0; 20]),
+            Err(KeyWrapError::InvalidLength(20))
+        );
+    }
+}
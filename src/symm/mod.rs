@@ -1,7 +1,9 @@
-mod aes;
+pub mod aes;
+pub mod keywrap;
 mod modes;
 pub mod padding;
-mod prng;
+pub mod prng;
+pub mod xts;
 use std::fmt;
 
 use padding::{get_pad, unpad, Padding};
@@ -14,8 +16,31 @@ use crate::random;
 pub enum Mode {
     None,
     ECB,
-    CBC { iv: Vec<u8> },
-    CTR { nonce: u64 },
+    CBC {
+        iv: Vec<u8>,
+    },
+    CTR {
+        nonce: u64,
+    },
+    /// `segment_bits` is 1, 8 or 128.
+    CFB {
+        iv: Vec<u8>,
+        segment_bits: usize,
+    },
+    OFB {
+        iv: Vec<u8>,
+    },
+    PCBC {
+        iv: Vec<u8>,
+    },
+}
+
+impl Mode {
+    /// Whether the mode works as a stream cipher,
+    /// so its ciphertexts can be any length.
+    pub fn is_stream(&self) -> bool {
+        matches!(self, Mode::CFB { .. } | Mode::OFB { .. })
+    }
 }
 
 impl fmt::Display for Mode {
@@ -26,6 +51,11 @@ impl fmt::Display for Mode {
             Mode::CTR { nonce } => {
                 format!("CTR | Nonce: {:02x}", nonce)
             }
+            Mode::CFB { iv, segment_bits } => {
+                format!("CFB{} | IV: {:02x?}", segment_bits, iv.clone())
+            }
+            Mode::OFB { iv } => format!("OFB | IV: {:02x?}", iv.clone()),
+            Mode::PCBC { iv } => format!("PCBC | IV: {:02x?}", iv.clone()),
             Mode::None => "None".to_owned(),
         };
         write!(f, "{}", mode)
@@ -58,6 +88,11 @@ impl AESCiphertext {
             Mode::ECB => Self::encrypt_with_ecb(key, &padded_plain_text),
             Mode::CBC { iv } => Self::encrypt_with_cbc(key, &padded_plain_text, iv),
             Mode::CTR { nonce } => Self::encrypt_with_ctr(key, &padded_plain_text, *nonce),
+            Mode::CFB { iv, segment_bits } => {
+                Self::encrypt_with_cfb(key, &padded_plain_text, iv, *segment_bits)
+            }
+            Mode::OFB { iv } => Self::encrypt_with_ofb(key, &padded_plain_text, iv),
+            Mode::PCBC { iv } => Self::encrypt_with_pcbc(key, &padded_plain_text, iv),
             Mode::None => Self::encrypt_raw(key, &padded_plain_text),
         };
         Self {
@@ -68,7 +103,7 @@ impl AESCiphertext {
     }
 
     pub fn from_existing(bytes: Vec<u8>, mode: Mode, padding: Padding) -> Self {
-        if bytes.len() % 16 != 0 {
+        if !mode.is_stream() && !bytes.len().is_multiple_of(16) {
             panic!("can't create AES ciphertext which len is not a multiple of 16")
         }
         Self {
@@ -95,6 +130,11 @@ impl AESCiphertext {
             Mode::ECB => Self::decrypt_with_ecb(key, &self.bytes),
             Mode::CBC { iv } => Self::decrypt_with_cbc(key, &self.bytes, iv),
             Mode::CTR { nonce } => Self::decrypt_with_ctr(key, &self.bytes, *nonce),
+            Mode::CFB { iv, segment_bits } => {
+                Self::decrypt_with_cfb(key, &self.bytes, iv, *segment_bits)
+            }
+            Mode::OFB { iv } => Self::decrypt_with_ofb(key, &self.bytes, iv),
+            Mode::PCBC { iv } => Self::decrypt_with_pcbc(key, &self.bytes, iv),
             Mode::None => Self::decrypt_raw(key, &self.bytes),
         }
     }
@@ -117,6 +157,24 @@ impl AESCiphertext {
         ctr.encrypt(plain_text)
     }
 
+    fn encrypt_with_cfb(key: &[u8], plain_text: &[u8], iv: &[u8], segment_bits: usize) -> Vec<u8> {
+        let mut cipher = aes::Cipher::new(key);
+        let mut cfb = modes::CFB::new(&mut cipher, iv, segment_bits);
+        cfb.encrypt(plain_text)
+    }
+
+    fn encrypt_with_ofb(key: &[u8], plain_text: &[u8], iv: &[u8]) -> Vec<u8> {
+        let mut cipher = aes::Cipher::new(key);
+        let mut ofb = modes::OFB::new(&mut cipher, iv);
+        ofb.encrypt(plain_text)
+    }
+
+    fn encrypt_with_pcbc(key: &[u8], plain_text: &[u8], iv: &[u8]) -> Vec<u8> {
+        let mut cipher = aes::Cipher::new(key);
+        let mut pcbc = modes::PCBC::new(&mut cipher, iv);
+        pcbc.encrypt(plain_text)
+    }
+
     fn encrypt_raw(key: &[u8], plain_text: &[u8]) -> Vec<u8> {
         let mut cipher = aes::Cipher::new(key);
         cipher.set_state(plain_text);
@@ -141,6 +199,24 @@ impl AESCiphertext {
         ctr.decrypt(cipher_text)
     }
 
+    fn decrypt_with_cfb(key: &[u8], cipher_text: &[u8], iv: &[u8], segment_bits: usize) -> Vec<u8> {
+        let mut cipher = aes::Cipher::new(key);
+        let mut cfb = modes::CFB::new(&mut cipher, iv, segment_bits);
+        cfb.decrypt(cipher_text)
+    }
+
+    fn decrypt_with_ofb(key: &[u8], cipher_text: &[u8], iv: &[u8]) -> Vec<u8> {
+        let mut cipher = aes::Cipher::new(key);
+        let mut ofb = modes::OFB::new(&mut cipher, iv);
+        ofb.decrypt(cipher_text)
+    }
+
+    fn decrypt_with_pcbc(key: &[u8], cipher_text: &[u8], iv: &[u8]) -> Vec<u8> {
+        let mut cipher = aes::Cipher::new(key);
+        let mut pcbc = modes::PCBC::new(&mut cipher, iv);
+        pcbc.decrypt(cipher_text)
+    }
+
     fn decrypt_raw(key: &[u8], cipher_text: &[u8]) -> Vec<u8> {
         let mut cipher = aes::Cipher::new(key);
         cipher.set_state(cipher_text);
@@ -163,6 +239,11 @@ pub fn aes_encrypt(key: &[u8], plain_text: &[u8], mode: Mode, padding: Padding)
         Mode::ECB => AESCiphertext::encrypt_with_ecb(key, &padded_plain_text),
         Mode::CBC { iv } => AESCiphertext::encrypt_with_cbc(key, &padded_plain_text, iv),
         Mode::CTR { nonce } => AESCiphertext::encrypt_with_ctr(key, &padded_plain_text, *nonce),
+        Mode::CFB { iv, segment_bits } => {
+            AESCiphertext::encrypt_with_cfb(key, &padded_plain_text, iv, *segment_bits)
+        }
+        Mode::OFB { iv } => AESCiphertext::encrypt_with_ofb(key, &padded_plain_text, iv),
+        Mode::PCBC { iv } => AESCiphertext::encrypt_with_pcbc(key, &padded_plain_text, iv),
         Mode::None => AESCiphertext::encrypt_raw(key, &padded_plain_text),
     };
     bytes
@@ -173,11 +254,41 @@ pub fn aes_decrypt(key: &[u8], cipher_text: &[u8], mode: Mode, padding: Padding)
         Mode::ECB => AESCiphertext::decrypt_with_ecb(key, &cipher_text),
         Mode::CBC { iv } => AESCiphertext::decrypt_with_cbc(key, &cipher_text, iv),
         Mode::CTR { nonce } => AESCiphertext::decrypt_with_ctr(key, &cipher_text, *nonce),
+        Mode::CFB { iv, segment_bits } => {
+            AESCiphertext::decrypt_with_cfb(key, cipher_text, iv, *segment_bits)
+        }
+        Mode::OFB { iv } => AESCiphertext::decrypt_with_ofb(key, cipher_text, iv),
+        Mode::PCBC { iv } => AESCiphertext::decrypt_with_pcbc(key, cipher_text, iv),
         Mode::None => AESCiphertext::decrypt_raw(key, &cipher_text),
     };
     unpad(&padding, &plain_text).unwrap()
 }
 
+/// A message encrypted with the keystream of any PRNG.
+/// The key is the PRNG, seeded however that PRNG is seeded,
+/// and it's given again, seeded the same, to decrypt.
+#[derive(Clone)]
+pub struct PrngCipherText {
+    pub bytes: Vec<u8>,
+}
+
+impl PrngCipherText {
+    pub fn new<P: prng::PRNG + Clone>(prng: P, plain_text: &[u8]) -> Self {
+        let bytes = prng::Cipher::new(prng).encrypt(plain_text);
+        Self { bytes }
+    }
+
+    pub fn from_existing(bytes: Vec<u8>) -> Self {
+        Self { bytes }
+    }
+
+    pub fn decrypt<P: prng::PRNG + Clone>(&self, prng: P) -> Vec<u8> {
+        prng::Cipher::new(prng).decrypt(&self.bytes)
+    }
+}
+
+/// A `PrngCipherText` keyed with the seed of a Mersenne Twister,
+/// which is what the challenges use.
 #[derive(Clone)]
 pub struct MersenneTwisterCipherText {
     pub bytes: Vec<u8>,
@@ -185,10 +296,8 @@ pub struct MersenneTwisterCipherText {
 
 impl MersenneTwisterCipherText {
     pub fn new(key: u32, plain_text: &[u8]) -> Self {
-        let mt = Box::new(random::MersenneTwister::new(key));
-        let mut cipher = prng::Cipher::new(mt);
-        cipher.set_state(plain_text);
-        let bytes = cipher.encrypt();
+        let mt = random::MersenneTwister::new(key);
+        let bytes = PrngCipherText::new(mt, plain_text).bytes;
         Self { bytes }
     }
 
@@ -197,24 +306,22 @@ impl MersenneTwisterCipherText {
     }
 
     pub fn decrypt(&self, key: u32) -> Vec<u8> {
-        let mt = Box::new(random::MersenneTwister::new(key));
-        let mut cipher = prng::Cipher::new(mt);
-        cipher.set_state(&self.bytes);
-        cipher.decrypt()
+        let mt = random::MersenneTwister::new(key);
+        PrngCipherText::from_existing(self.bytes.clone()).decrypt(mt)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::encoding::hex;
+    use crate::encoding::hex::{self, Case};
 
     #[test]
     fn test_encrypt_aes_hex() {
         let key = hex::from_string(&"000102030405060708090a0b0c0d0e0f").unwrap();
         let plain = hex::from_string(&"00112233445566778899aabbccddeeff").unwrap();
         let result = AESCiphertext::new(&key, &plain, Mode::None, Padding::PKCS7);
-        let result = hex::to_string(&result.bytes).to_ascii_lowercase();
+        let result = hex::encode(&result.bytes, Case::Lower);
         assert_eq!(result, "69c4e0d86a7b0430d8cdb78070b4c55a")
     }
 
@@ -225,10 +332,103 @@ mod tests {
         let cipher_text =
             AESCiphertext::from_existing(cipher_text_bytes, Mode::None, Padding::None);
         let result = cipher_text.decrypt(&key);
-        let plain_text = hex::to_string(&result).to_ascii_lowercase();
+        let plain_text = hex::encode(&result, Case::Lower);
         assert_eq!(plain_text, "00112233445566778899aabbccddeeff");
     }
 
+    #[test]
+    fn test_no_padding_keeps_length() {
+        let key = b"YELLOW SUBMARINE";
+        let plain = [7; 32];
+        let ecb = aes_encrypt(key, &plain, Mode::ECB, Padding::None);
+        assert_eq!(ecb.len(), plain.len());
+        let iv = vec![0; 16];
+        let ofb = aes_encrypt(key, &plain[..20], Mode::OFB { iv }, Padding::None);
+        assert_eq!(ofb.len(), 20);
+    }
+
+    #[test]
+    fn test_new_modes_round_trip() {
+        let key = hex::from_string("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+        let iv = hex::from_string("000102030405060708090a0b0c0d0e0f").unwrap();
+        let plain = b"the quick brown fox jumps over the lazy dog";
+        let modes = vec![
+            (
+                Mode::CFB {
+                    iv: iv.clone(),
+                    segment_bits: 1,
+                },
+                Padding::None,
+            ),
+            (
+                Mode::CFB {
+                    iv: iv.clone(),
+                    segment_bits: 8,
+                },
+                Padding::None,
+            ),
+            (
+                Mode::CFB {
+                    iv: iv.clone(),
+                    segment_bits: 128,
+                },
+                Padding::None,
+            ),
+            (Mode::OFB { iv: iv.clone() }, Padding::None),
+            (Mode::PCBC { iv: iv.clone() }, Padding::PKCS7),
+        ];
+        for (mode, padding) in modes {
+            let cipher_text = AESCiphertext::new(&key, plain, mode.clone(), padding.clone());
+            let expected_len = if mode.is_stream() { plain.len() } else { 48 };
+            assert_eq!(cipher_text.bytes.len(), expected_len, "{}", mode);
+            assert_eq!(cipher_text.decrypt(&key), plain.to_vec(), "{}", mode);
+
+            let existing = AESCiphertext::from_existing(
+                cipher_text.bytes.clone(),
+                mode.clone(),
+                padding.clone(),
+            );
+            assert_eq!(existing.decrypt(&key), plain.to_vec(), "{}", mode);
+            let bytes = aes_encrypt(&key, plain, mode.clone(), padding.clone());
+            assert_eq!(aes_decrypt(&key, &bytes, mode, padding), plain.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_ofb_vector() {
+        // SP 800-38A, F.4.1, first two blocks
+        let key = hex::from_string("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+        let iv = hex::from_string("000102030405060708090a0b0c0d0e0f").unwrap();
+        let plain =
+            hex::from_string("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51")
+                .unwrap();
+        let result = AESCiphertext::new(&key, &plain, Mode::OFB { iv }, Padding::None);
+        assert_eq!(
+            hex::encode(&result.bytes, Case::Lower),
+            "3b3fd92eb72dad20333449f8e83cfb4a7789508d16918f03f53c52dac54ed825"
+        );
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_any_prng() {
+        use crate::random::{pcg::Pcg32, xorshift::Xorshift128Plus};
+        let plain = b"the same message, under different PRNGs";
+
+        let pcg = PrngCipherText::new(Pcg32::new(42, 54), plain);
+        assert_eq!(pcg.decrypt(Pcg32::new(42, 54)), plain.to_vec());
+        assert_ne!(pcg.decrypt(Pcg32::new(43, 54)), plain.to_vec());
+
+        let xorshift = PrngCipherText::new(Xorshift128Plus::new([1, 2]), plain);
+        assert_ne!(xorshift.bytes, pcg.bytes);
+        assert_eq!(
+            xorshift.decrypt(Xorshift128Plus::new([1, 2])),
+            plain.to_vec()
+        );
+
+        let mt = PrngCipherText::new(random::MersenneTwister::new(42), plain);
+        assert_eq!(mt.bytes, MersenneTwisterCipherText::new(42, plain).bytes);
+    }
+
     #[test]
     fn test_encrypt_decrypt_prng_hex() {
         let key = 42;
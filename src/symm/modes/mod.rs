@@ -168,12 +168,194 @@ impl<'a> CTR<'a> {
     }
 }
 
+/// A CFB mode of operation, with segments of 1, 8 or as many bits as
+/// the block. The cipher encrypts a shift register, starting at the IV,
+/// and the first bits of that are XORed with the next segment of the message.
+/// The ciphertext segment is then shifted into the register.
+/// Works as a stream cipher, so the message doesn't need padding.
+#[allow(clippy::upper_case_acronyms)]
+pub struct CFB<'a> {
+    cipher: &'a mut dyn BlockCipher,
+    block_size: usize,
+    iv: &'a [u8],
+    segment_bits: usize,
+}
+
+impl<'a> CFB<'a> {
+    pub fn new(cipher: &'a mut dyn BlockCipher, iv: &'a [u8], segment_bits: usize) -> CFB<'a> {
+        let block_size = cipher.get_block_size();
+        if block_size != iv.len() {
+            panic!("IV must be the safe length as the block of the cipher");
+        }
+        if segment_bits != 1 && segment_bits != 8 && segment_bits != block_size * 8 {
+            panic!("CFB segments can only be 1, 8 or {} bits", block_size * 8);
+        }
+        CFB {
+            cipher,
+            block_size,
+            iv,
+            segment_bits,
+        }
+    }
+
+    pub fn encrypt(&mut self, msg: &[u8]) -> Vec<u8> {
+        self.process(msg, false)
+    }
+
+    pub fn decrypt(&mut self, cipher_text: &[u8]) -> Vec<u8> {
+        self.process(cipher_text, true)
+    }
+
+    fn process(&mut self, input: &[u8], decrypting: bool) -> Vec<u8> {
+        let mut register = self.iv.to_vec();
+        let mut output = Vec::with_capacity(input.len());
+
+        if self.segment_bits == 1 {
+            for &byte in input {
+                let mut out = 0;
+                for bit in (0..8).rev() {
+                    self.cipher.set_state(&register);
+                    let keystream = self.cipher.encrypt()[0] >> 7;
+                    let in_bit = (byte >> bit) & 1;
+                    let out_bit = in_bit ^ keystream;
+                    out |= out_bit << bit;
+                    let cipher_bit = if decrypting { in_bit } else { out_bit };
+                    shift_bit_in(&mut register, cipher_bit);
+                }
+                output.push(out);
+            }
+            return output;
+        }
+
+        let segment_size = self.segment_bits / 8;
+        for segment in input.chunks(segment_size) {
+            self.cipher.set_state(&register);
+            let keystream = self.cipher.encrypt();
+            let out = xor(segment, &keystream);
+            let cipher_segment = if decrypting { segment } else { &out };
+            register.drain(..segment_size);
+            register.extend_from_slice(cipher_segment);
+            // a last, shorter segment leaves the register too short, but it's the last
+            register.resize(self.block_size, 0);
+            output.extend_from_slice(&out);
+        }
+        output
+    }
+}
+
+/// Shifts the register one bit to the left, putting `bit` in the last one.
+fn shift_bit_in(register: &mut [u8], bit: u8) {
+    let len = register.len();
+    for i in 0..len {
+        let carry = if i + 1 < len {
+            register[i + 1] >> 7
+        } else {
+            bit
+        };
+        register[i] = (register[i] << 1) | carry;
+    }
+}
+
+/// An OFB mode of operation. The cipher encrypts the IV, then its
+/// own output over and over, and that keystream is XORed with the message.
+/// Like CTR, decrypting is encrypting, and no padding is needed.
+#[allow(clippy::upper_case_acronyms)]
+pub struct OFB<'a> {
+    cipher: &'a mut dyn BlockCipher,
+    block_size: usize,
+    iv: &'a [u8],
+}
+
+impl<'a> OFB<'a> {
+    pub fn new(cipher: &'a mut dyn BlockCipher, iv: &'a [u8]) -> OFB<'a> {
+        let block_size = cipher.get_block_size();
+        if block_size != iv.len() {
+            panic!("IV must be the safe length as the block of the cipher");
+        }
+        OFB {
+            cipher,
+            block_size,
+            iv,
+        }
+    }
+
+    pub fn encrypt(&mut self, msg: &[u8]) -> Vec<u8> {
+        let mut cipher_text = Vec::with_capacity(msg.len());
+        let mut keystream = self.iv.to_vec();
+
+        for chunk in msg.chunks(self.block_size) {
+            self.cipher.set_state(&keystream);
+            keystream = self.cipher.encrypt();
+            cipher_text.append(&mut xor(chunk, &keystream));
+        }
+
+        cipher_text
+    }
+
+    pub fn decrypt(&mut self, cipher_text: &[u8]) -> Vec<u8> {
+        self.encrypt(cipher_text)
+    }
+}
+
+/// A PCBC mode of operation. Like CBC, but what is XORed with the next
+/// plain block is both the last plain block and the last cipher block,
+/// so an error in the ciphertext garbles everything after it.
+/// The message must be a multiple of the block size.
+#[allow(clippy::upper_case_acronyms)]
+pub struct PCBC<'a> {
+    cipher: &'a mut dyn BlockCipher,
+    block_size: usize,
+    iv: &'a [u8],
+}
+
+impl<'a> PCBC<'a> {
+    pub fn new(cipher: &'a mut dyn BlockCipher, iv: &'a [u8]) -> PCBC<'a> {
+        let block_size = cipher.get_block_size();
+        if block_size != iv.len() {
+            panic!("IV must be the safe length as the block of the cipher");
+        }
+        PCBC {
+            cipher,
+            block_size,
+            iv,
+        }
+    }
+
+    pub fn encrypt(&mut self, msg: &[u8]) -> Vec<u8> {
+        let mut cipher_text = Vec::with_capacity(msg.len());
+        let mut feedback = self.iv.to_vec();
+
+        for plain_block in msg.chunks_exact(self.block_size) {
+            self.cipher.set_state(&xor(plain_block, &feedback));
+            let cipher_block = self.cipher.encrypt();
+            feedback = xor(plain_block, &cipher_block);
+            cipher_text.extend_from_slice(&cipher_block);
+        }
+
+        cipher_text
+    }
+
+    pub fn decrypt(&mut self, cipher_text: &[u8]) -> Vec<u8> {
+        let mut plain_text = Vec::with_capacity(cipher_text.len());
+        let mut feedback = self.iv.to_vec();
+
+        for cipher_block in cipher_text.chunks_exact(self.block_size) {
+            self.cipher.set_state(cipher_block);
+            let plain_block = xor(&self.cipher.decrypt(), &feedback);
+            feedback = xor(&plain_block, cipher_block);
+            plain_text.extend_from_slice(&plain_block);
+        }
+
+        plain_text
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::aes;
     use super::*;
     use crate::encoding::base64;
-    use crate::encoding::hex;
+    use crate::encoding::hex::{self, Case};
     use std::str;
 
     #[test]
@@ -184,7 +366,7 @@ mod tests {
         let mut ecb = ECB::new(&mut cipher);
         let plain_text = hex::from_string("00112233445566778899aabbccddeeff").unwrap();
         let result = ecb.encrypt(&plain_text);
-        let hex_result = hex::to_string(&result).to_ascii_lowercase();
+        let hex_result = hex::encode(&result, Case::Lower);
         assert_eq!(hex_result, "69c4e0d86a7b0430d8cdb78070b4c55a");
     }
 
@@ -198,7 +380,7 @@ mod tests {
             hex::from_string("00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff")
                 .unwrap();
         let result = ecb.encrypt(&plain_text);
-        let hex_result = hex::to_string(&result).to_ascii_lowercase();
+        let hex_result = hex::encode(&result, Case::Lower);
         assert_eq!(
             hex_result,
             "69c4e0d86a7b0430d8cdb78070b4c55a69c4e0d86a7b0430d8cdb78070b4c55a"
@@ -215,7 +397,7 @@ mod tests {
             hex::from_string("69c4e0d86a7b0430d8cdb78070b4c55a69c4e0d86a7b0430d8cdb78070b4c55a")
                 .unwrap();
         let result = ecb.decrypt(&cipher_text);
-        let hex_result = hex::to_string(&result).to_ascii_lowercase();
+        let hex_result = hex::encode(&result, Case::Lower);
         assert_eq!(
             hex_result,
             "00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff"
@@ -322,4 +504,111 @@ mod tests {
         let plaintext = ctr.decrypt(&ciphertext);
         str::from_utf8(&plaintext).unwrap();
     }
+
+    // SP 800-38A, appendix F
+    const SP_KEY: &str = "2b7e151628aed2a6abf7158809cf4f3c";
+    const SP_IV: &str = "000102030405060708090a0b0c0d0e0f";
+    const SP_PLAIN: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+                            30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+    fn sp_vectors() -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        (
+            hex::from_string(SP_KEY).unwrap(),
+            hex::from_string(SP_IV).unwrap(),
+            hex::from_string(SP_PLAIN).unwrap(),
+        )
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_CFB128() {
+        let (key, iv, plain) = sp_vectors();
+        let expected = hex::from_string(
+            "3b3fd92eb72dad20333449f8e83cfb4ac8a64537a0b3a93fcde3cdad9f1ce58b\
+             26751f67a3cbb140b1808cf187a4f4dfc04b05357c5d1c0eeac4c66f9ff7f2e6",
+        )
+        .unwrap();
+        let mut cipher = aes::Cipher::new(&key);
+        let mut cfb = CFB::new(&mut cipher, &iv, 128);
+        assert_eq!(cfb.encrypt(&plain), expected);
+        assert_eq!(cfb.decrypt(&expected), plain);
+        // as a stream cipher, a shorter message is a prefix
+        assert_eq!(cfb.encrypt(&plain[..20]), &expected[..20]);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_CFB8() {
+        let (key, iv, plain) = sp_vectors();
+        let expected = hex::from_string("3b79424c9c0dd436bace9e0ed4586a4f32b9").unwrap();
+        let mut cipher = aes::Cipher::new(&key);
+        let mut cfb = CFB::new(&mut cipher, &iv, 8);
+        assert_eq!(cfb.encrypt(&plain[..18]), expected);
+        assert_eq!(cfb.decrypt(&expected), &plain[..18]);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_CFB1() {
+        let (key, iv, plain) = sp_vectors();
+        // 0110101111000001 becomes 0110100010110011
+        let expected = [0x68, 0xb3];
+        let mut cipher = aes::Cipher::new(&key);
+        let mut cfb = CFB::new(&mut cipher, &iv, 1);
+        assert_eq!(cfb.encrypt(&plain[..2]), expected);
+        assert_eq!(cfb.decrypt(&expected), &plain[..2]);
+    }
+
+    #[test]
+    #[should_panic]
+    #[allow(non_snake_case)]
+    fn test_CFB_invalid_segment() {
+        let mut cipher = aes::Cipher::new(&[0; 16]);
+        CFB::new(&mut cipher, &[0; 16], 16);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_OFB() {
+        let (key, iv, plain) = sp_vectors();
+        let expected = hex::from_string(
+            "3b3fd92eb72dad20333449f8e83cfb4a7789508d16918f03f53c52dac54ed825\
+             9740051e9c5fecf64344f7a82260edcc304c6528f659c77866a510d9c1d6ae5e",
+        )
+        .unwrap();
+        let mut cipher = aes::Cipher::new(&key);
+        let mut ofb = OFB::new(&mut cipher, &iv);
+        assert_eq!(ofb.encrypt(&plain), expected);
+        assert_eq!(ofb.decrypt(&expected[..30]), &plain[..30]);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_PCBC() {
+        // SP 800-38A has no PCBC, but its first block is the one of CBC
+        // and the rest can be built with ECB
+        let (key, iv, plain) = sp_vectors();
+        let mut cipher = aes::Cipher::new(&key);
+        let ciphertext = PCBC::new(&mut cipher, &iv).encrypt(&plain);
+        assert_eq!(
+            hex::encode(&ciphertext[..16], Case::Lower),
+            "7649abac8119b246cee98e9b12e9197d"
+        );
+        let mut ecb = ECB::new(&mut cipher);
+        let feedback = xor(&plain[..16], &ciphertext[..16]);
+        let second = ecb.encrypt(&xor(&plain[16..32], &feedback));
+        assert_eq!(second, &ciphertext[16..32]);
+
+        assert_eq!(PCBC::new(&mut cipher, &iv).decrypt(&ciphertext), plain);
+
+        // an error garbles every block after it
+        let mut broken = ciphertext.clone();
+        broken[20] ^= 1;
+        let decrypted = PCBC::new(&mut cipher, &iv).decrypt(&broken);
+        assert_eq!(decrypted[..16], plain[..16]);
+        for block in 1..4 {
+            let range = block * 16..block * 16 + 16;
+            assert_ne!(decrypted[range.clone()], plain[range]);
+        }
+    }
 }
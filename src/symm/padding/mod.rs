@@ -9,7 +9,7 @@ pub enum Padding {
 pub fn get_pad(with: &Padding, data: &[u8], desired_len: u8) -> Option<Vec<u8>> {
     match with {
         Padding::PKCS7 => pad_PKCS7(data, desired_len),
-        Padding::None => Some(data.to_owned()),
+        Padding::None => Some(Vec::new()),
     }
 }
 
@@ -90,6 +90,16 @@ mod tests {
         assert_eq!(pad_PKCS7(data.as_bytes(), 16).unwrap(), expected)
     }
 
+    #[test]
+    fn test_no_padding_is_empty() {
+        // the pad is appended to the data, so it must not be the data itself
+        assert_eq!(get_pad(&Padding::None, b"YELLOW S", 16), Some(Vec::new()));
+        assert_eq!(
+            unpad(&Padding::None, b"YELLOW S"),
+            Some(b"YELLOW S".to_vec())
+        );
+    }
+
     #[test]
     #[allow(non_snake_case)]
     fn unpad_with_PKCS7() {
@@ -1,39 +1,194 @@
+use crate::random::glibc::GlibcRandom;
+use crate::random::java::JavaRandom;
+use crate::random::pcg::Pcg32;
+use crate::random::xorshift::Xorshift128Plus;
 use crate::random::MersenneTwister;
 
 // A PRNG is just an iterator. next()
 // should gives a random numbers.
-pub trait PRNG: Iterator<Item = usize> {}
+pub trait PRNG: Iterator<Item = usize> {
+    /// How many bytes of each number given by next() are random.
+    fn width(&self) -> usize {
+        4
+    }
+}
 
 impl PRNG for MersenneTwister {}
+impl PRNG for JavaRandom {}
+impl PRNG for GlibcRandom {
+    // only 31 bits, so the top byte would always have its MSB clear
+    fn width(&self) -> usize {
+        3
+    }
+}
+impl PRNG for Pcg32 {}
+impl PRNG for Xorshift128Plus {
+    fn width(&self) -> usize {
+        8
+    }
+}
+
+/// The order in which the bytes of each
+/// number are fed into the keystream.
+#[derive(Clone, Copy, Debug)]
+pub enum Endian {
+    Big,
+    Little,
+}
 
-pub struct Cipher {
-    prng: Box<dyn PRNG>,
-    state: Vec<u8>,
+/// A stream cipher XORing the message against the output of a PRNG.
+/// Every random byte of every number is used, so a 32 bit PRNG
+/// gives four bytes of keystream per call to next().
+pub struct Cipher<P: PRNG + Clone> {
+    // the PRNG as it was at position zero, to seek backwards
+    initial: P,
+    prng: P,
+    endian: Endian,
+    word: Vec<u8>,
+    used: usize,
+    position: usize,
 }
 
-impl Cipher {
-    pub fn new(prng: Box<dyn PRNG>) -> Self {
+impl<P: PRNG + Clone> Cipher<P> {
+    pub fn new(prng: P) -> Self {
+        Self::new_with_endian(prng, Endian::Little)
+    }
+
+    pub fn new_with_endian(prng: P, endian: Endian) -> Self {
         Cipher {
+            initial: prng.clone(),
             prng,
-            state: Vec::new(),
+            endian,
+            word: Vec::new(),
+            used: 0,
+            position: 0,
+        }
+    }
+
+    pub fn encrypt(&mut self, msg: &[u8]) -> Vec<u8> {
+        let keystream = self.keystream(msg.len());
+        msg.iter().zip(keystream).map(|(m, k)| m ^ k).collect()
+    }
+
+    pub fn decrypt(&mut self, msg: &[u8]) -> Vec<u8> {
+        self.encrypt(msg)
+    }
+
+    /// Returns the next `len` bytes of keystream.
+    pub fn keystream(&mut self, len: usize) -> Vec<u8> {
+        let mut keystream = Vec::with_capacity(len);
+        while keystream.len() < len {
+            if self.used == self.word.len() {
+                self.next_word();
+            }
+            let take = (len - keystream.len()).min(self.word.len() - self.used);
+            keystream.extend_from_slice(&self.word[self.used..self.used + take]);
+            self.used += take;
         }
+        self.position += len;
+        keystream
     }
-    pub fn set_state(&mut self, msg: &[u8]) {
-        self.state = msg.to_vec()
+
+    /// The position in the keystream, in bytes.
+    pub fn position(&self) -> usize {
+        self.position
     }
 
-    pub fn encrypt(&mut self) -> Vec<u8> {
-        for i in 0..self.state.len() {
-            self.state[i] = self.state[i] ^ self.prng.next().unwrap() as u8;
+    /// Moves to an arbitrary position in the keystream, in bytes.
+    /// Seeking backwards replays the PRNG from the start.
+    pub fn seek(&mut self, position: usize) {
+        if position < self.position {
+            self.prng = self.initial.clone();
+            self.word.clear();
+            self.used = 0;
+            self.position = 0;
+        }
+
+        // finish the current word, skip the
+        // whole words in between and then
+        // whatever is left of the last one
+        let mut skip = position - self.position;
+        let left_in_word = self.word.len() - self.used;
+        if skip <= left_in_word {
+            self.used += skip;
+            self.position = position;
+            return;
         }
-        self.state.clone()
+        skip -= left_in_word;
+        let width = self.prng.width();
+        for _ in 0..skip / width {
+            self.prng.next();
+        }
+        self.next_word();
+        self.used = skip % width;
+        self.position = position;
     }
 
-    pub fn decrypt(mut self) -> Vec<u8> {
-        let mut plain = Vec::with_capacity(self.state.len());
-        for i in 0..self.state.len() {
-            plain.push(self.state[i] ^ self.prng.next().unwrap() as u8)
+    fn next_word(&mut self) {
+        let width = self.prng.width();
+        let number = self.prng.next().unwrap() as u64;
+        let bytes = match self.endian {
+            Endian::Big => number.to_be_bytes()[8 - width..].to_vec(),
+            Endian::Little => number.to_le_bytes()[..width].to_vec(),
+        };
+        self.word = bytes;
+        self.used = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keystream_uses_whole_words() {
+        let mut mt = MersenneTwister::new(5489);
+        let mut cipher = Cipher::new(MersenneTwister::new(5489));
+        let mut expected = (mt.next().unwrap() as u32).to_le_bytes().to_vec();
+        expected.extend(&(mt.next().unwrap() as u32).to_le_bytes()[..2]);
+        assert_eq!(cipher.keystream(6), expected);
+    }
+
+    #[test]
+    fn test_keystream_big_endian() {
+        let mut xorshift = Xorshift128Plus::new([1, 2]);
+        let mut cipher = Cipher::new_with_endian(Xorshift128Plus::new([1, 2]), Endian::Big);
+        let expected = xorshift.next_u64().to_be_bytes();
+        assert_eq!(cipher.keystream(8), expected);
+    }
+
+    #[test]
+    fn test_glibc_keystream_msbs_vary() {
+        let keystream = Cipher::new(GlibcRandom::new(1)).keystream(1200);
+        for offset in 0..4 {
+            let msbs: Vec<u8> = keystream
+                .iter()
+                .skip(offset)
+                .step_by(4)
+                .map(|b| b >> 7)
+                .collect();
+            assert!(msbs.contains(&0) && msbs.contains(&1), "offset {}", offset);
+        }
+    }
+
+    #[test]
+    fn test_seek() {
+        let mut cipher = Cipher::new(Pcg32::new(42, 54));
+        let keystream = cipher.keystream(64);
+        for &position in [13, 4, 0, 63, 21, 22].iter() {
+            cipher.seek(position);
+            assert_eq!(cipher.keystream(64 - position), &keystream[position..]);
         }
-        plain
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let plain = b"Attack at dawn, bring the sandwiches";
+        let cipher_text = Cipher::new(JavaRandom::new(7)).encrypt(plain);
+        assert_ne!(&cipher_text[..], &plain[..]);
+        assert_eq!(
+            Cipher::new(JavaRandom::new(7)).decrypt(&cipher_text),
+            &plain[..]
+        );
     }
 }
@@ -0,0 +1,277 @@
+//! XTS-AES, the tweakable mode of IEEE 1619 for encrypting disks.
+//! Every sector is encrypted on its own, with a tweak made from its
+//! number, so the same data in two sectors doesn't look the same and
+//! sectors can be read and written in any order. Sectors which are not
+//! a multiple of 16 bytes are handled with ciphertext stealing, so the
+//! ciphertext is never longer than the plaintext.
+use super::aes;
+use std::{error, fmt};
+
+type Result<T> = std::result::Result<T, XtsError>;
+
+const BLOCK_SIZE: usize = 16;
+
+/// Why a sector could not be encrypted or decrypted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum XtsError {
+    /// The key is two AES keys, so 32 or 64 bytes.
+    InvalidKeyLength(usize),
+    /// Sectors must be at least a block long.
+    InvalidLength(usize),
+}
+
+impl fmt::Display for XtsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            XtsError::InvalidKeyLength(len) => write!(f, "invalid key length {}", len),
+            XtsError::InvalidLength(len) => write!(f, "sector of {} bytes is too short", len),
+        }
+    }
+}
+
+impl error::Error for XtsError {}
+
+/// XTS-AES-128 or XTS-AES-256, depending on the key.
+pub struct Xts {
+    data: aes::Cipher,
+    tweak: aes::Cipher,
+}
+
+impl Xts {
+    /// `key` is the key for the data followed by the key for the tweak,
+    /// both 16 or both 32 bytes.
+    pub fn new(key: &[u8]) -> Result<Self> {
+        if key.len() != 32 && key.len() != 64 {
+            return Err(XtsError::InvalidKeyLength(key.len()));
+        }
+        let (data_key, tweak_key) = key.split_at(key.len() / 2);
+        Ok(Self {
+            data: aes::Cipher::new(data_key),
+            tweak: aes::Cipher::new(tweak_key),
+        })
+    }
+
+    /// Encrypts the sector, or data unit, number `sector`.
+    pub fn encrypt_sector(&mut self, sector: u128, plain_text: &[u8]) -> Result<Vec<u8>> {
+        self.process(sector, plain_text, true)
+    }
+
+    /// Decrypts the sector, or data unit, number `sector`.
+    pub fn decrypt_sector(&mut self, sector: u128, cipher_text: &[u8]) -> Result<Vec<u8>> {
+        self.process(sector, cipher_text, false)
+    }
+
+    /// Encrypts an image split in sectors of `sector_size` bytes, numbered
+    /// from `first_sector`. The last sector can be shorter, but not under 16 bytes.
+    pub fn encrypt_sectors(
+        &mut self,
+        first_sector: u128,
+        sector_size: usize,
+        plain_text: &[u8],
+    ) -> Result<Vec<u8>> {
+        self.process_sectors(first_sector, sector_size, plain_text, true)
+    }
+
+    /// Decrypts what `encrypt_sectors` encrypted.
+    pub fn decrypt_sectors(
+        &mut self,
+        first_sector: u128,
+        sector_size: usize,
+        cipher_text: &[u8],
+    ) -> Result<Vec<u8>> {
+        self.process_sectors(first_sector, sector_size, cipher_text, false)
+    }
+
+    fn process_sectors(
+        &mut self,
+        first_sector: u128,
+        sector_size: usize,
+        data: &[u8],
+        encrypting: bool,
+    ) -> Result<Vec<u8>> {
+        if sector_size < BLOCK_SIZE {
+            return Err(XtsError::InvalidLength(sector_size));
+        }
+        let mut output = Vec::with_capacity(data.len());
+        for (i, sector) in data.chunks(sector_size).enumerate() {
+            output.append(&mut self.process(first_sector + i as u128, sector, encrypting)?);
+        }
+        Ok(output)
+    }
+
+    fn process(&mut self, sector: u128, data: &[u8], encrypting: bool) -> Result<Vec<u8>> {
+        if data.len() < BLOCK_SIZE {
+            return Err(XtsError::InvalidLength(data.len()));
+        }
+        self.tweak.set_state(&sector.to_le_bytes());
+        let mut tweak = self.tweak.encrypt();
+
+        let full_blocks = data.len() / BLOCK_SIZE;
+        let remainder = data.len() % BLOCK_SIZE;
+        // with stealing, the last full block is done with the partial one
+        let plain_blocks = if remainder == 0 {
+            full_blocks
+        } else {
+            full_blocks - 1
+        };
+
+        let mut output = Vec::with_capacity(data.len());
+        for block in data.chunks_exact(BLOCK_SIZE).take(plain_blocks) {
+            output.extend_from_slice(&self.block(block, &tweak, encrypting));
+            multiply_by_alpha(&mut tweak);
+        }
+        if remainder == 0 {
+            return Ok(output);
+        }
+
+        // Ciphertext stealing: the last full block is done with the tweak
+        // of the partial one when decrypting, and the other way around.
+        let last_full = &data[plain_blocks * BLOCK_SIZE..full_blocks * BLOCK_SIZE];
+        let partial = &data[full_blocks * BLOCK_SIZE..];
+        let mut next_tweak = tweak;
+        multiply_by_alpha(&mut next_tweak);
+        let (first_tweak, second_tweak) = if encrypting {
+            (tweak, next_tweak)
+        } else {
+            (next_tweak, tweak)
+        };
+
+        let stolen = self.block(last_full, &first_tweak, encrypting);
+        let mut joined = partial.to_vec();
+        joined.extend_from_slice(&stolen[remainder..]);
+        output.extend_from_slice(&self.block(&joined, &second_tweak, encrypting));
+        output.extend_from_slice(&stolen[..remainder]);
+        Ok(output)
+    }
+
+    /// XEX: XOR with the tweak, encrypt or decrypt, XOR with the tweak again.
+    fn block(&mut self, block: &[u8], tweak: &[u8; 16], encrypting: bool) -> [u8; 16] {
+        let mut state = [0; BLOCK_SIZE];
+        for (s, (b, t)) in state.iter_mut().zip(block.iter().zip(tweak)) {
+            *s = b ^ t;
+        }
+        self.data.set_state(&state);
+        let mut result = if encrypting {
+            self.data.encrypt()
+        } else {
+            self.data.decrypt()
+        };
+        for (r, t) in result.iter_mut().zip(tweak) {
+            *r ^= t;
+        }
+        result
+    }
+}
+
+/// Multiplies the tweak by x in GF(2^128), modulo x^128 + x^7 + x^2 + x + 1,
+/// the tweak being a little-endian number.
+fn multiply_by_alpha(tweak: &mut [u8; 16]) {
+    let mut carry = 0;
+    for byte in tweak.iter_mut() {
+        let next_carry = *byte >> 7;
+        *byte = (*byte << 1) | carry;
+        carry = next_carry;
+    }
+    if carry == 1 {
+        tweak[0] ^= 0x87;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::hex;
+
+    fn h(s: &str) -> Vec<u8> {
+        hex::from_string(s).unwrap()
+    }
+
+    #[test]
+    fn test_ieee_1619_vectors() {
+        // vectors 1, 2, 3 and 15 of IEEE 1619-2007, appendix B,
+        // which writes the sequence numbers as little-endian bytes
+        let vectors = [
+            (
+                "00000000000000000000000000000000",
+                "00000000000000000000000000000000",
+                0,
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "917cf69ebd68b2ec9b9fe9a3eadda692cd43d2f59598ed858c02c2652fbf922e",
+            ),
+            (
+                "11111111111111111111111111111111",
+                "22222222222222222222222222222222",
+                0x3333333333,
+                "4444444444444444444444444444444444444444444444444444444444444444",
+                "c454185e6a16936e39334038acef838bfb186fff7480adc4289382ecd6d394f0",
+            ),
+            (
+                "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0",
+                "22222222222222222222222222222222",
+                0x3333333333,
+                "4444444444444444444444444444444444444444444444444444444444444444",
+                "af85336b597afc1a900b2eb21ec949d292df4c047e0b21532186a5971a227a89",
+            ),
+            (
+                "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0",
+                "bfbebdbcbbbab9b8b7b6b5b4b3b2b1b0",
+                0x123456789a,
+                "000102030405060708090a0b0c0d0e0f10",
+                "6c1625db4671522d3d7599601de7ca09ed",
+            ),
+        ];
+        for (key1, key2, sector, plain, cipher) in vectors.iter() {
+            let mut xts = Xts::new(&[h(key1), h(key2)].concat()).unwrap();
+            assert_eq!(xts.encrypt_sector(*sector, &h(plain)), Ok(h(cipher)));
+            assert_eq!(xts.decrypt_sector(*sector, &h(cipher)), Ok(h(plain)));
+        }
+    }
+
+    #[test]
+    fn test_ciphertext_stealing_round_trip() {
+        let mut xts = Xts::new(&[7; 64]).unwrap();
+        let plain: Vec<u8> = (0..=255).collect();
+        for len in 16..100 {
+            let cipher_text = xts.encrypt_sector(5, &plain[..len]).unwrap();
+            assert_eq!(cipher_text.len(), len);
+            assert_eq!(xts.decrypt_sector(5, &cipher_text).unwrap(), &plain[..len]);
+        }
+    }
+
+    #[test]
+    fn test_sectors() {
+        let mut xts = Xts::new(&[9; 32]).unwrap();
+        let image = vec![0; 512 * 3 + 100];
+        let encrypted = xts.encrypt_sectors(10, 512, &image).unwrap();
+        assert_eq!(encrypted.len(), image.len());
+        // same data, different sectors
+        assert_ne!(encrypted[..512], encrypted[512..1024]);
+        assert_eq!(
+            encrypted[512..1024],
+            xts.encrypt_sector(11, &image[512..1024]).unwrap()[..]
+        );
+        assert_eq!(xts.decrypt_sectors(10, 512, &encrypted).unwrap(), image);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Xts::new(&[0; 16]).err(),
+            Some(XtsError::InvalidKeyLength(16))
+        );
+        let mut xts = Xts::new(&[0; 32]).unwrap();
+        assert_eq!(
+            xts.encrypt_sector(0, &[0; 15]),
+            Err(XtsError::InvalidLength(15))
+        );
+        assert_eq!(
+            xts.encrypt_sectors(0, 8, &[0; 32]),
+            Err(XtsError::InvalidLength(8))
+        );
+        // a last sector shorter than a block
+        assert_eq!(
+            xts.encrypt_sectors(0, 512, &[0; 520]),
+            Err(XtsError::InvalidLength(8))
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use crypto::encoding::base64;
 use crypto::frequency;
 use crypto::random::{self, MersenneTwister, Random};
-use crypto::symm::MersenneTwisterCipherText;
+use crypto::symm::{prng, MersenneTwisterCipherText};
 use crypto::symm::{padding::Padding, AESCiphertext, Mode};
 use crypto::{bytes};
 use std::{convert::TryInto, fs, str};
@@ -269,19 +269,11 @@ pub fn create_mt1993_stream_cipher_break_it() {
     // at the end???
 
     let mut my_seed = 0;
+    let known_start = ciphertext.len() - 14;
     for i in 0..u16::MAX {
-        let mut try_mt = MersenneTwister::new(i.into());
-        for _ in 0..ciphertext.len() - 14 {
-            try_mt.next();
-        }
-        let mut oks = 0;
-        for i in ciphertext.len() - 14..ciphertext.len() {
-            if (ciphertext[i] ^ try_mt.next().unwrap() as u8) != *"A".as_bytes().get(0).unwrap() {
-                break;
-            }
-            oks += 1;
-        }
-        if oks == 14 {
+        let mut try_cipher = prng::Cipher::new(MersenneTwister::new(i.into()));
+        try_cipher.seek(known_start);
+        if try_cipher.decrypt(&ciphertext[known_start..]) == "A".repeat(14).as_bytes() {
             my_seed = i;
             break;
         }